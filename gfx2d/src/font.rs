@@ -0,0 +1,275 @@
+use super::*;
+use image::{GenericImageView, Rgba, RgbaImage as Image};
+use rusttype::{self, point, Scale};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+const PADDING: i32 = 2;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct TextStyle {
+    pub color: Color,
+    pub align: Align,
+    pub shadow: Option<Color>,
+}
+
+#[derive(Debug, Clone)]
+struct Glyph {
+    sprite: Option<Sprite>,
+    offset: Vec2,
+    advance: f32,
+}
+
+// Glyphs rasterized into a spritesheet. Sizes and positions are in the same units as
+// sprites: pixels of the source divided by the pixel ratio, so a font rasterized with
+// the ratio between screen pixels and game units stays sharp when drawn in game units.
+#[derive(Debug, Clone)]
+pub struct Font {
+    glyphs: HashMap<char, Glyph>,
+    ascent: f32,
+    line_height: f32,
+}
+
+fn charset() -> impl Iterator<Item = char> {
+    (32u8..127).chain(160u8..=255).map(char::from)
+}
+
+impl TextStyle {
+    pub fn new(color: Color) -> TextStyle {
+        TextStyle {
+            color,
+            align: Align::Left,
+            shadow: None,
+        }
+    }
+
+    pub fn align(self, align: Align) -> TextStyle {
+        TextStyle { align, ..self }
+    }
+
+    pub fn shadow(self, color: Color) -> TextStyle {
+        TextStyle {
+            shadow: Some(color),
+            ..self
+        }
+    }
+}
+
+impl Font {
+    pub fn empty() -> Font {
+        Font {
+            glyphs: HashMap::new(),
+            ascent: 0.0,
+            line_height: 0.0,
+        }
+    }
+
+    // Rasterizes Latin-1 from a TrueType/OpenType file at `size` units tall. Returns
+    // None when the file can't be read or parsed.
+    pub fn from_ttf<P: AsRef<Path>>(
+        context: &mut Gfx2dContext,
+        filename: P,
+        size: f32,
+        pixel_ratio: Vec2,
+    ) -> Option<Font> {
+        let data = fs::read(filename).ok()?;
+        let font = rusttype::Font::try_from_vec(data)?;
+        let scale = Scale {
+            x: size * pixel_ratio.x,
+            y: size * pixel_ratio.y,
+        };
+
+        let v_metrics = font.v_metrics(scale);
+        let mut images: Vec<(Image, Vec2)> = Vec::new();
+        let mut glyphs: Vec<(char, Glyph, bool)> = Vec::new();
+
+        for c in charset() {
+            let glyph = font.glyph(c);
+
+            if glyph.id().0 == 0 {
+                continue;
+            }
+
+            let glyph = glyph.scaled(scale).positioned(point(0.0, 0.0));
+            let advance = glyph.unpositioned().h_metrics().advance_width / pixel_ratio.x;
+            let mut offset = Vec2::zero();
+            let bounds = glyph.pixel_bounding_box();
+
+            if let Some(bb) = bounds {
+                let mut img = Image::new(bb.width() as u32, bb.height() as u32);
+
+                glyph.draw(|x, y, coverage| {
+                    let alpha = (coverage.min(1.0) * 255.0) as u8;
+                    img.put_pixel(x, y, Rgba([255, 255, 255, alpha]));
+                });
+
+                offset = vec2(
+                    bb.min.x as f32 / pixel_ratio.x,
+                    bb.min.y as f32 / pixel_ratio.y,
+                );
+
+                images.push((img, pixel_ratio));
+            }
+
+            let glyph = Glyph {
+                sprite: None,
+                offset,
+                advance,
+            };
+
+            glyphs.push((c, glyph, bounds.is_some()));
+        }
+
+        Some(Font {
+            glyphs: Self::attach_sprites(context, glyphs, images),
+            ascent: v_metrics.ascent / pixel_ratio.y,
+            line_height: (v_metrics.ascent - v_metrics.descent + v_metrics.line_gap)
+                / pixel_ratio.y,
+        })
+    }
+
+    // Bitmap font from an image holding a 16x16 grid of cells, one per Latin-1 code.
+    // Glyphs are trimmed to their opaque columns so the font isn't monospaced. Returns
+    // None when the image doesn't exist.
+    pub fn from_bitmap<P: AsRef<Path>>(
+        context: &mut Gfx2dContext,
+        filename: P,
+        color_key: Option<Color>,
+        pixel_ratio: Vec2,
+    ) -> Option<Font> {
+        let filename = filename.as_ref();
+
+        if !filename.exists() {
+            return None;
+        }
+
+        let mut img = gfx2d_extra::load_image_rgba(filename);
+
+        if let Some(color) = color_key {
+            gfx2d_extra::remove_color_key(&mut img, color);
+        }
+
+        let (cw, ch) = (img.width() / 16, img.height() / 16);
+        let space = cw as f32 / (2.0 * pixel_ratio.x);
+        let mut images: Vec<(Image, Vec2)> = Vec::new();
+        let mut glyphs: Vec<(char, Glyph, bool)> = Vec::new();
+
+        for c in charset() {
+            let (x, y) = ((c as u32 % 16) * cw, (c as u32 / 16) * ch);
+            let cell = img.view(x, y, cw, ch).to_image();
+            let opaque = |col: &u32| (0..ch).any(|row| cell.get_pixel(*col, row)[3] > 0);
+            let left = (0..cw).find(opaque);
+            let right = (0..cw).rev().find(opaque);
+
+            let mut glyph = Glyph {
+                sprite: None,
+                offset: Vec2::zero(),
+                advance: space,
+            };
+
+            if let (Some(left), Some(right)) = (left, right) {
+                let width = right - left + 1;
+                images.push((cell.view(left, 0, width, ch).to_image(), pixel_ratio));
+                glyph.advance = (width + 1) as f32 / pixel_ratio.x;
+            }
+
+            glyphs.push((c, glyph, left.is_some()));
+        }
+
+        Some(Font {
+            glyphs: Self::attach_sprites(context, glyphs, images),
+            ascent: 0.0,
+            line_height: ch as f32 / pixel_ratio.y,
+        })
+    }
+
+    // Packs the glyph images into one spritesheet and hands the sprites to the glyphs
+    // that have an image, in order.
+    fn attach_sprites(
+        context: &mut Gfx2dContext,
+        glyphs: Vec<(char, Glyph, bool)>,
+        images: Vec<(Image, Vec2)>,
+    ) -> HashMap<char, Glyph> {
+        let sheet = Spritesheet::from_images(context, PADDING, FilterMethod::Bilinear, images);
+        let mut sprites = sheet.sprites.into_iter();
+
+        glyphs
+            .into_iter()
+            .map(|(c, mut glyph, visible)| {
+                if visible {
+                    glyph.sprite = sprites.next();
+                }
+
+                (c, glyph)
+            })
+            .collect()
+    }
+
+    pub fn line_height(&self) -> f32 {
+        self.line_height
+    }
+
+    // Width of the widest line and height of all lines.
+    pub fn measure(&self, text: &str) -> Vec2 {
+        let lines = text.split('\n');
+        let (count, width) = lines.fold((0, 0.0f32), |(count, width), line| {
+            (count + 1, width.max(self.line_width(line)))
+        });
+
+        vec2(width, count as f32 * self.line_height)
+    }
+
+    fn line_width(&self, line: &str) -> f32 {
+        line.chars()
+            .map(|c| self.glyph(c).map_or(0.0, |g| g.advance))
+            .sum()
+    }
+
+    // unknown characters show as '?'
+    fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs.get(&c).or_else(|| self.glyphs.get(&'?'))
+    }
+}
+
+impl DrawBatch {
+    // Draws text with its top at `pos.y`. Depending on the alignment `pos.x` is where
+    // lines start, are centered or end. Lines are separated by '\n'.
+    pub fn add_text(&mut self, font: &Font, text: &str, pos: Vec2, style: &TextStyle) {
+        if let Some(shadow) = style.shadow {
+            let style = TextStyle {
+                color: shadow,
+                shadow: None,
+                ..*style
+            };
+
+            self.add_text(font, text, pos + vec2(1.0, 1.0), &style);
+        }
+
+        for (i, line) in text.split('\n').enumerate() {
+            let width = font.line_width(line);
+            let x = match style.align {
+                Align::Left => pos.x,
+                Align::Center => pos.x - (0.5 * width).round(),
+                Align::Right => pos.x - width,
+            };
+
+            let mut pen = vec2(x, pos.y + font.ascent + i as f32 * font.line_height);
+
+            for glyph in line.chars().filter_map(|c| font.glyph(c)) {
+                if let Some(ref sprite) = glyph.sprite {
+                    self.add_sprite(sprite, style.color, Transform::Pos(pen + glyph.offset));
+                }
+
+                pen.x += glyph.advance;
+            }
+        }
+    }
+}
@@ -9,12 +9,14 @@ extern crate gfx_device_gl;
 extern crate gfx_window_glutin;
 extern crate glutin;
 extern crate image;
+extern crate rusttype;
 
 include!("gfx_types.rs");
 
 mod batch;
 mod color;
 mod context;
+mod font;
 mod spritesheet;
 mod texture;
 mod transform;
@@ -30,6 +32,9 @@ pub use color::rgba;
 pub use context::Gfx2dContext;
 pub use context::Vertex;
 pub use context::vertex;
+pub use font::Align;
+pub use font::Font;
+pub use font::TextStyle;
 pub use gfx::texture::FilterMethod;
 pub use gfx::texture::WrapMode;
 pub use spritesheet::Sprite;
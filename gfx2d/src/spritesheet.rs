@@ -81,31 +81,49 @@ impl Spritesheet {
         filter: FilterMethod,
         info: &[SpriteInfo],
     ) -> Spritesheet {
-        if info.is_empty() {
+        let images = info
+            .iter()
+            .map(|sprite_info| {
+                let mut img = if sprite_info.filename.exists() {
+                    gfx2d_extra::load_image_rgba(&sprite_info.filename)
+                } else {
+                    Image::from_pixel(1, 1, image::Rgba([0u8; 4]))
+                };
+
+                if let Some(color) = sprite_info.color_key {
+                    gfx2d_extra::remove_color_key(&mut img, color);
+                }
+
+                (img, sprite_info.pixel_ratio)
+            })
+            .collect();
+
+        Self::from_images(context, padding, filter, images)
+    }
+
+    // Packs images already in memory, each with its pixel ratio. Sprites come out in
+    // the same order as the images.
+    pub fn from_images(
+        context: &mut Gfx2dContext,
+        padding: i32,
+        filter: FilterMethod,
+        images: Vec<(Image, Vec2)>,
+    ) -> Spritesheet {
+        if images.is_empty() {
             return Spritesheet::empty();
         }
 
         let max_size = context.max_texture_size() as i32;
-        let mut images: Vec<Image> = Vec::with_capacity(info.len());
-        let mut sprites: Vec<Sprite> = Vec::with_capacity(info.len());
-        let mut rects: Vec<Rect> = Vec::with_capacity(info.len());
-
-        for (index, sprite_info) in info.iter().enumerate() {
-            let mut img = if sprite_info.filename.exists() {
-                gfx2d_extra::load_image_rgba(&sprite_info.filename)
-            } else {
-                Image::from_pixel(1, 1, image::Rgba([0u8; 4]))
-            };
-
-            if let Some(color) = sprite_info.color_key {
-                gfx2d_extra::remove_color_key(&mut img, color);
-            }
+        let mut sprites: Vec<Sprite> = Vec::with_capacity(images.len());
+        let mut rects: Vec<Rect> = Vec::with_capacity(images.len());
+        let mut packed: Vec<Image> = Vec::with_capacity(images.len());
 
+        for (index, (mut img, pixel_ratio)) in images.into_iter().enumerate() {
             gfx2d_extra::premultiply_image(&mut img);
 
             sprites.push(Sprite {
-                width: img.width() as f32 / sprite_info.pixel_ratio.x,
-                height: img.height() as f32 / sprite_info.pixel_ratio.y,
+                width: img.width() as f32 / pixel_ratio.x,
+                height: img.height() as f32 / pixel_ratio.y,
                 texcoords_x: (0.0, 0.0),
                 texcoords_y: (0.0, 0.0),
                 texture: None,
@@ -127,7 +145,7 @@ impl Spritesheet {
                 data: (index, 0),
             });
 
-            images.push(img);
+            packed.push(img);
         }
 
         let mut sheets: Vec<(i32, i32)> = Vec::new();
@@ -141,7 +159,7 @@ impl Spritesheet {
         for rc in &rects {
             let image_index = rc.data.0;
             let sheet_index = rc.data.1;
-            sheets[sheet_index].copy_from(&images[image_index], rc.x as u32, rc.y as u32);
+            sheets[sheet_index].copy_from(&packed[image_index], rc.x as u32, rc.y as u32);
         }
 
         let textures: Vec<Texture> = sheets
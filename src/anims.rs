@@ -1,7 +1,6 @@
 use super::*;
 use lazy_static;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::BufRead;
 use std::path::PathBuf;
 
 lazy_static! {
@@ -138,9 +137,8 @@ impl AnimData {
         let mut path = PathBuf::from("assets/anims/");
         path.push(file_name);
 
-        let file = File::open(&path).expect("Error opening animation file.");
+        let mut buf = read_asset(&path).expect("Error opening animation file.");
         let mut line = String::new();
-        let mut buf = BufReader::new(file);
         let mut frames: Vec<AnimFrame> = Vec::new();
         let mut positions: Vec<Vec2> = Vec::new();
 
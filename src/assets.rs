@@ -0,0 +1,17 @@
+use super::*;
+use std::io::{self, Cursor};
+use std::path::Path;
+
+// Skeletons, animations and maps are read whole from Soldat's data in assets/. Tests
+// get generated stand-ins instead, so they don't depend on the data being copied in.
+#[cfg(not(test))]
+pub fn read_asset(path: &Path) -> io::Result<Cursor<Vec<u8>>> {
+    std::fs::read(path).map(Cursor::new)
+}
+
+#[cfg(test)]
+pub fn read_asset(path: &Path) -> io::Result<Cursor<Vec<u8>>> {
+    testing::asset(path)
+        .map(Cursor::new)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, path.display().to_string()))
+}
@@ -0,0 +1,234 @@
+use super::*;
+use glutin::{ElementState, KeyboardInput, MouseButton, VirtualKeyCode};
+use ini::Ini;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Input {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+}
+
+// Maps keyboard keys and mouse buttons to soldier actions. An action can have any
+// number of inputs and stays active while at least one of them is held.
+pub struct Bindings {
+    bindings: Vec<(Input, Action)>,
+    pressed: Vec<Input>,
+}
+
+static KEYS: &[(&str, VirtualKeyCode)] = &[
+    ("A", VirtualKeyCode::A),
+    ("B", VirtualKeyCode::B),
+    ("C", VirtualKeyCode::C),
+    ("D", VirtualKeyCode::D),
+    ("E", VirtualKeyCode::E),
+    ("F", VirtualKeyCode::F),
+    ("G", VirtualKeyCode::G),
+    ("H", VirtualKeyCode::H),
+    ("I", VirtualKeyCode::I),
+    ("J", VirtualKeyCode::J),
+    ("K", VirtualKeyCode::K),
+    ("L", VirtualKeyCode::L),
+    ("M", VirtualKeyCode::M),
+    ("N", VirtualKeyCode::N),
+    ("O", VirtualKeyCode::O),
+    ("P", VirtualKeyCode::P),
+    ("Q", VirtualKeyCode::Q),
+    ("R", VirtualKeyCode::R),
+    ("S", VirtualKeyCode::S),
+    ("T", VirtualKeyCode::T),
+    ("U", VirtualKeyCode::U),
+    ("V", VirtualKeyCode::V),
+    ("W", VirtualKeyCode::W),
+    ("X", VirtualKeyCode::X),
+    ("Y", VirtualKeyCode::Y),
+    ("Z", VirtualKeyCode::Z),
+    ("1", VirtualKeyCode::Key1),
+    ("2", VirtualKeyCode::Key2),
+    ("3", VirtualKeyCode::Key3),
+    ("4", VirtualKeyCode::Key4),
+    ("5", VirtualKeyCode::Key5),
+    ("6", VirtualKeyCode::Key6),
+    ("7", VirtualKeyCode::Key7),
+    ("8", VirtualKeyCode::Key8),
+    ("9", VirtualKeyCode::Key9),
+    ("0", VirtualKeyCode::Key0),
+    ("Space", VirtualKeyCode::Space),
+    ("Return", VirtualKeyCode::Return),
+    ("Back", VirtualKeyCode::Back),
+    ("LShift", VirtualKeyCode::LShift),
+    ("RShift", VirtualKeyCode::RShift),
+    ("LControl", VirtualKeyCode::LControl),
+    ("RControl", VirtualKeyCode::RControl),
+    ("LAlt", VirtualKeyCode::LAlt),
+    ("RAlt", VirtualKeyCode::RAlt),
+    ("Up", VirtualKeyCode::Up),
+    ("Down", VirtualKeyCode::Down),
+    ("Left", VirtualKeyCode::Left),
+    ("Right", VirtualKeyCode::Right),
+    ("Insert", VirtualKeyCode::Insert),
+    ("Delete", VirtualKeyCode::Delete),
+    ("Home", VirtualKeyCode::Home),
+    ("End", VirtualKeyCode::End),
+    ("PageUp", VirtualKeyCode::PageUp),
+    ("PageDown", VirtualKeyCode::PageDown),
+    ("Numpad0", VirtualKeyCode::Numpad0),
+    ("Numpad1", VirtualKeyCode::Numpad1),
+    ("Numpad2", VirtualKeyCode::Numpad2),
+    ("Numpad3", VirtualKeyCode::Numpad3),
+    ("Numpad4", VirtualKeyCode::Numpad4),
+    ("Numpad5", VirtualKeyCode::Numpad5),
+    ("Numpad6", VirtualKeyCode::Numpad6),
+    ("Numpad7", VirtualKeyCode::Numpad7),
+    ("Numpad8", VirtualKeyCode::Numpad8),
+    ("Numpad9", VirtualKeyCode::Numpad9),
+    ("NumpadEnter", VirtualKeyCode::NumpadEnter),
+    ("Comma", VirtualKeyCode::Comma),
+    ("Period", VirtualKeyCode::Period),
+    ("Slash", VirtualKeyCode::Slash),
+    ("Backslash", VirtualKeyCode::Backslash),
+    ("Semicolon", VirtualKeyCode::Semicolon),
+    ("Apostrophe", VirtualKeyCode::Apostrophe),
+    ("LBracket", VirtualKeyCode::LBracket),
+    ("RBracket", VirtualKeyCode::RBracket),
+    ("Minus", VirtualKeyCode::Minus),
+    ("Equals", VirtualKeyCode::Equals),
+];
+
+impl Input {
+    // Key names as in KEYS, mouse buttons as Mouse1 (left), Mouse2 (right), Mouse3
+    // (middle) and Mouse4 and up for extra buttons.
+    pub fn from_name(name: &str) -> Option<Input> {
+        let name = name.trim();
+
+        if let Some(n) = name
+            .to_lowercase()
+            .strip_prefix("mouse")
+            .and_then(|n| n.parse::<u8>().ok())
+        {
+            return match n {
+                0 => None,
+                1 => Some(Input::Mouse(MouseButton::Left)),
+                2 => Some(Input::Mouse(MouseButton::Right)),
+                3 => Some(Input::Mouse(MouseButton::Middle)),
+                n => Some(Input::Mouse(MouseButton::Other(n))),
+            };
+        }
+
+        KEYS.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|&(_, code)| Input::Key(code))
+    }
+}
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        let defaults: &[(&str, Action)] = &[
+            ("A", Action::Left),
+            ("D", Action::Right),
+            ("W", Action::Jump),
+            ("S", Action::Crouch),
+            ("X", Action::Prone),
+            ("Mouse1", Action::Fire),
+            ("Mouse2", Action::Jet),
+            ("G", Action::Grenade),
+            ("Q", Action::ChangeWeapon),
+            ("E", Action::ThrowWeapon),
+            ("F", Action::DropWeapon),
+            ("R", Action::Reload),
+            ("V", Action::FlagThrow),
+            ("C", Action::Use),
+            ("T", Action::Chat),
+            ("Y", Action::TeamChat),
+            ("Z", Action::Radio),
+        ];
+
+        Bindings {
+            bindings: defaults
+                .iter()
+                .filter_map(|&(name, action)| Input::from_name(name).map(|input| (input, action)))
+                .collect(),
+            pressed: Vec::new(),
+        }
+    }
+}
+
+impl Bindings {
+    pub fn load_from_file(file_name: &str) -> Bindings {
+        let mut bindings = Bindings::default();
+
+        if let Ok(cfg) = Ini::load_from_file(file_name) {
+            bindings.load_data(&cfg);
+        }
+
+        bindings
+    }
+
+    // Reads the [BINDINGS] section, one key per action with a comma separated list of
+    // inputs, e.g. `Jump = W, Space`. Actions missing from the file keep their default
+    // inputs, an empty list unbinds the action.
+    pub fn load_data(&mut self, cfg: &Ini) {
+        if let Some(data) = cfg.section(Some("BINDINGS".to_owned())) {
+            for action in Action::values() {
+                if let Some(value) = data.get(action.name()) {
+                    self.bindings.retain(|&(_, a)| a != *action);
+
+                    for input in value.split(',').filter_map(Input::from_name) {
+                        self.bindings.push((input, *action));
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn key_input(&mut self, input: &KeyboardInput, control: &mut Control) {
+        if let Some(key) = input.virtual_keycode {
+            self.input(Input::Key(key), input.state, control);
+        }
+    }
+
+    // The chat a key press opens, if it's bound to one of the chat actions.
+    pub fn chat_key(&self, input: &KeyboardInput) -> Option<ChatKind> {
+        let key = Input::Key(input.virtual_keycode?);
+
+        if input.state != ElementState::Pressed {
+            return None;
+        }
+
+        self.bindings
+            .iter()
+            .filter(|&&(bound, _)| bound == key)
+            .find_map(|&(_, action)| action.chat_kind())
+    }
+
+    pub fn mouse_input(&mut self, state: ElementState, button: MouseButton, control: &mut Control) {
+        self.input(Input::Mouse(button), state, control);
+    }
+
+    // Lets go of everything, for when input goes elsewhere.
+    pub fn release_all(&mut self, control: &mut Control) {
+        self.pressed.clear();
+
+        for action in Action::values() {
+            control.set_action(*action, false);
+        }
+    }
+
+    fn input(&mut self, input: Input, state: ElementState, control: &mut Control) {
+        self.pressed.retain(|i| *i != input);
+
+        if state == ElementState::Pressed {
+            self.pressed.push(input);
+        }
+
+        for &(bound, action) in &self.bindings {
+            if bound == input {
+                let active = self
+                    .bindings
+                    .iter()
+                    .any(|&(i, a)| a == action && self.pressed.contains(&i));
+
+                control.set_action(action, active);
+            }
+        }
+    }
+}
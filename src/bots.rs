@@ -0,0 +1,508 @@
+use super::*;
+
+const SECOND: i32 = 60;
+const SIGHT_RANGE: f32 = 600.0;
+const REACH_RADIUS: f32 = 25.0;
+const JUMP_HEIGHT: f32 = 30.0;
+const STUCK_TIME: i32 = SECOND;
+const REPATH_TIME: i32 = SECOND * 3;
+const LOW_HEALTH: f32 = 0.4;
+const CLOSE_RANGE: f32 = 150.0;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BotDifficulty {
+    Easy,
+    Normal,
+    Hard,
+    Impossible,
+}
+
+// The brain of a computer controlled soldier. Every tick it looks at the world and
+// fills in the soldier's `Control` the same way keyboard and mouse do for a human.
+#[derive(Debug, Clone)]
+pub struct Bot {
+    pub num: usize,
+    pub difficulty: BotDifficulty,
+    target: Option<usize>,
+    reaction_timer: i32,
+    route: Option<Route>,
+    last_waypoint: Option<usize>,
+    stuck_timer: i32,
+}
+
+impl BotDifficulty {
+    pub fn from_name(name: &str) -> Option<BotDifficulty> {
+        match name.trim().to_lowercase().as_str() {
+            "easy" => Some(BotDifficulty::Easy),
+            "normal" => Some(BotDifficulty::Normal),
+            "hard" => Some(BotDifficulty::Hard),
+            "impossible" => Some(BotDifficulty::Impossible),
+            _ => None,
+        }
+    }
+
+    // ticks between spotting an enemy and pulling the trigger
+    pub fn reaction_time(&self) -> i32 {
+        match *self {
+            BotDifficulty::Easy => 45,
+            BotDifficulty::Normal => 25,
+            BotDifficulty::Hard => 12,
+            BotDifficulty::Impossible => 1,
+        }
+    }
+
+    // largest angle in radians the aim can be off by
+    pub fn aim_error(&self) -> f32 {
+        match *self {
+            BotDifficulty::Easy => 0.2,
+            BotDifficulty::Normal => 0.1,
+            BotDifficulty::Hard => 0.05,
+            BotDifficulty::Impossible => 0.0,
+        }
+    }
+}
+
+impl Bot {
+    pub fn new(num: usize, difficulty: BotDifficulty) -> Bot {
+        Bot {
+            num,
+            difficulty,
+            target: None,
+            reaction_timer: 0,
+            route: None,
+            last_waypoint: None,
+            stuck_timer: 0,
+        }
+    }
+
+    // Random weapons within the server's limits, the default loadout if nothing fits.
+    pub fn choose_loadout(
+        limits: &WeaponLimits,
+        soldier: &Soldier,
+        soldiers: &[Soldier],
+    ) -> Loadout {
+        let pick = |kinds: &[WeaponKind]| {
+            let allowed: Vec<WeaponKind> = kinds
+                .iter()
+                .filter(|kind| limits.accepts(**kind, soldier.team, soldier.num, soldiers))
+                .cloned()
+                .collect();
+
+            iif!(
+                allowed.is_empty(),
+                None,
+                Some(allowed[rand::random::<usize>() % allowed.len()])
+            )
+        };
+
+        let default = Loadout::default();
+
+        Loadout {
+            primary: pick(Loadout::primaries()).unwrap_or(default.primary),
+            secondary: pick(Loadout::secondaries()).unwrap_or(default.secondary),
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        control: &mut Control,
+        soldier: &Soldier,
+        soldiers: &[Soldier],
+        state: &MainState,
+    ) {
+        let was_jetting = control.jets;
+
+        control.left = false;
+        control.right = false;
+        control.up = false;
+        control.down = false;
+        control.fire = false;
+        control.jets = false;
+        control.change = false;
+        control.reload = false;
+        control.flag_throw = false;
+
+        if soldier.dead_meat {
+            self.target = None;
+            self.route = None;
+            return;
+        }
+
+        let eye = soldier.skeleton.pos(12);
+        let target = self.find_target(eye, soldier, soldiers, &state.map);
+
+        if target != self.target {
+            self.target = target;
+            self.reaction_timer = self.difficulty.reaction_time();
+        }
+
+        if self.reaction_timer > 0 {
+            self.reaction_timer -= 1;
+        }
+
+        let destination = self.destination(soldier, soldiers, state);
+        let (mut aim, moving) = self.navigate(control, soldier, destination, state, was_jetting);
+
+        if let Some(enemy) = self
+            .target
+            .and_then(|num| soldiers.iter().find(|s| s.num == num))
+        {
+            let to_enemy = enemy.body_center() - eye;
+            let error = (rand::random::<f32>() * 2.0 - 1.0) * self.difficulty.aim_error();
+            let (sin, cos) = error.sin_cos();
+            aim = eye
+                + vec2(
+                    to_enemy.x * cos - to_enemy.y * sin,
+                    to_enemy.x * sin + to_enemy.y * cos,
+                );
+
+            let weapon = soldier.primary_weapon();
+            let close = distance(eye, enemy.body_center()) < CLOSE_RANGE;
+
+            if weapon.ammo_count == 0 && close && soldier.secondary_weapon().ammo_count > 0 {
+                control.change = true;
+            } else {
+                control.fire = self.reaction_timer == 0;
+            }
+        } else if soldier.primary_weapon().ammo_count < soldier.primary_weapon().ammo / 2 {
+            control.reload = true;
+        }
+
+        control.mouse_aim_x = aim.x.round() as i32;
+        control.mouse_aim_y = aim.y.round() as i32;
+
+        // unstuck: jump, and after a while route around the connection
+        if moving && soldier.particle.velocity.x.abs() < 0.1 && soldier.on_ground {
+            self.stuck_timer += 1;
+
+            if self.stuck_timer > STUCK_TIME {
+                control.up = true;
+            }
+
+            if self.stuck_timer > REPATH_TIME {
+                self.stuck_timer = 0;
+
+                let query = PathQuery::for_soldier(soldier);
+                let replanned = match (self.route.as_mut(), self.last_waypoint) {
+                    (Some(route), Some(from)) => state.paths.replan(route, from, &query),
+                    _ => false,
+                };
+
+                if !replanned {
+                    self.route = None;
+                }
+            }
+        } else {
+            self.stuck_timer = 0;
+        }
+    }
+
+    fn find_target(
+        &self,
+        eye: Vec2,
+        soldier: &Soldier,
+        soldiers: &[Soldier],
+        map: &MapFile,
+    ) -> Option<usize> {
+        soldiers
+            .iter()
+            .filter(|s| s.num != soldier.num && s.active && !s.dead_meat)
+            .filter(|s| !s.is_teammate(soldier.team, soldier.num))
+            .filter(|s| distance(eye, s.body_center()) < SIGHT_RANGE)
+            .filter(|s| map.line_of_sight(eye, s.body_center(), soldier.team))
+            .min_by(|a, b| {
+                let da = distance(eye, a.body_center());
+                let db = distance(eye, b.body_center());
+                da.partial_cmp(&db).unwrap()
+            })
+            .map(|s| s.num)
+    }
+
+    // Where the bot wants to go: a medikit when hurt, the game mode's objective, or
+    // else the closest enemy.
+    fn destination(
+        &self,
+        soldier: &Soldier,
+        soldiers: &[Soldier],
+        state: &MainState,
+    ) -> Option<Vec2> {
+        let pos = soldier.particle.pos;
+        let closest = |points: &mut dyn Iterator<Item = Vec2>| {
+            points.min_by(|a, b| distance(pos, *a).partial_cmp(&distance(pos, *b)).unwrap())
+        };
+
+        if soldier.health < LOW_HEALTH * Soldier::max_health(state.realistic_mode) {
+            let medikit = closest(
+                &mut state.things.iter().filter_map(|thing| match thing.kind {
+                    ThingKind::Kit(KitKind::Medikit) if thing.active => Some(thing.particle.pos),
+                    _ => None,
+                }),
+            );
+
+            if medikit.is_some() {
+                return medikit;
+            }
+        }
+
+        Self::objective(soldier, soldiers, state).or_else(|| {
+            closest(
+                &mut soldiers
+                    .iter()
+                    .filter(|s| s.num != soldier.num && !s.dead_meat)
+                    .filter(|s| !s.is_teammate(soldier.team, soldier.num))
+                    .map(|s| s.particle.pos),
+            )
+        })
+    }
+
+    fn objective(soldier: &Soldier, soldiers: &[Soldier], state: &MainState) -> Option<Vec2> {
+        let flags = &state.flags;
+        let carrying = flags.iter().any(|f| f.holder == Some(soldier.num));
+        let holder_pos = |flag: &Flag| {
+            flag.holder
+                .and_then(|num| soldiers.iter().find(|s| s.num == num))
+                .map(|s| s.particle.pos)
+        };
+
+        match state.round.mode {
+            GameMode::CaptureTheFlag | GameMode::Infiltration => {
+                if carrying {
+                    return state
+                        .round
+                        .capture_points
+                        .iter()
+                        .find(|&&(team, _)| team == soldier.team)
+                        .map(|&(_, pos)| pos);
+                }
+
+                // bring the own flag back first, chasing whoever took it
+                if let Some(flag) = flags
+                    .iter()
+                    .find(|f| f.team == soldier.team && !f.at_base())
+                {
+                    return holder_pos(flag).or_else(|| Some(flag.pos()));
+                }
+
+                // infiltration defenders stay with their flag
+                if state.round.mode == GameMode::Infiltration && soldier.team == Team::Bravo {
+                    return flags
+                        .iter()
+                        .find(|f| f.team == soldier.team)
+                        .map(|f| f.base);
+                }
+
+                flags
+                    .iter()
+                    .find(|f| f.team != soldier.team && f.holder.is_none())
+                    .map(|f| f.pos())
+            }
+            GameMode::HoldTheFlag | GameMode::Pointmatch => flags
+                .iter()
+                .filter(|f| f.holder != Some(soldier.num))
+                .find(|f| {
+                    f.holder.is_none()
+                        || soldiers.iter().any(|s| {
+                            Some(s.num) == f.holder && !s.is_teammate(soldier.team, soldier.num)
+                        })
+                })
+                .map(|f| holder_pos(f).unwrap_or_else(|| f.pos())),
+            GameMode::Rambomatch if !soldier.holds_bow() => state
+                .things
+                .iter()
+                .find(|thing| match thing.kind {
+                    ThingKind::Weapon(weapon) => {
+                        thing.active && weapon.is_any(&[WeaponKind::Bow, WeaponKind::FlameBow])
+                    }
+                    _ => false,
+                })
+                .map(|thing| thing.particle.pos),
+            _ => None,
+        }
+    }
+
+    // Moves towards the destination along the map's waypoints, or straight at it on
+    // maps without any. Returns where to look when there's nothing to shoot at and
+    // whether the bot is trying to move.
+    fn navigate(
+        &mut self,
+        control: &mut Control,
+        soldier: &Soldier,
+        destination: Option<Vec2>,
+        state: &MainState,
+        was_jetting: bool,
+    ) -> (Vec2, bool) {
+        let map = &state.map;
+        let pos = soldier.particle.pos;
+
+        let destination = match destination {
+            Some(destination) => destination,
+            None => return (pos + vec2(f32::from(soldier.direction) * 100.0, 0.0), false),
+        };
+
+        let mut next = destination;
+        let mut jet = false;
+
+        if !map.waypoints.is_empty() {
+            let query = PathQuery::for_soldier(soldier);
+            let goal = state.paths.nearest(destination, &query);
+
+            let stale = self
+                .route
+                .as_ref()
+                .is_none_or(|route| route.finished() || Some(route.goal) != goal);
+
+            if stale {
+                let from = state.paths.nearest(pos, &query);
+                self.last_waypoint = from;
+                self.route = match (from, goal) {
+                    (Some(from), Some(to)) => state.paths.route(from, to, &query),
+                    _ => None,
+                };
+            }
+
+            if let Some(route) = self.route.as_mut() {
+                while let Some(i) = route.next() {
+                    let waypoint = &map.waypoints[i];
+
+                    if distance(pos, vec2(waypoint.x as f32, waypoint.y as f32)) < REACH_RADIUS {
+                        self.last_waypoint = Some(i);
+                        route.advance();
+                    } else {
+                        break;
+                    }
+                }
+
+                // a waypoint's keys are the ones that get the bot there
+                if let Some(i) = route.next() {
+                    let waypoint = &map.waypoints[i];
+                    next = vec2(waypoint.x as f32, waypoint.y as f32);
+                    control.up |= waypoint.up && soldier.on_ground;
+                    control.down = waypoint.down;
+                    jet = waypoint.jetpack;
+                }
+            }
+        }
+
+        let delta = next - pos;
+
+        if delta.x.abs() > REACH_RADIUS / 2.0 {
+            control.left = delta.x < 0.0;
+            control.right = delta.x > 0.0;
+        }
+
+        if delta.y < -JUMP_HEIGHT {
+            control.up |= soldier.on_ground;
+            jet = true;
+        }
+
+        // keep jetting once started, otherwise wait on the ground for a full tank
+        // before trying to cross a gap
+        if jet && soldier.jets_count > 0 {
+            control.jets = was_jetting || soldier.jets_count >= map.start_jet / 2;
+        }
+
+        (next, control.left || control.right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::*;
+
+    fn add_bot(state: &MainState, soldiers: &mut Vec<Soldier>, bots: &mut Vec<Bot>, team: Team) {
+        let num = add_soldier(state, soldiers, team);
+        bots.push(Bot::new(num, BotDifficulty::Impossible));
+    }
+
+    // Puts everyone on the same spot so they start out in each other's sight.
+    fn face_off(state: &MainState, soldiers: &mut [Soldier]) {
+        let spawn = state.map.team_spawnpoint(Team::None);
+        let spawn = MapSpawnpoint {
+            active: true,
+            x: spawn.x,
+            y: spawn.y,
+            team: spawn.team,
+        };
+
+        for soldier in soldiers.iter_mut() {
+            soldier.respawn(&spawn, state.realistic_mode);
+        }
+    }
+
+    #[test]
+    fn bots_follow_waypoints_to_the_enemy_flag() {
+        let mut state = test_state(GameMode::CaptureTheFlag);
+        let mut soldiers = Vec::new();
+        let mut bots = Vec::new();
+        let mut emitter = Vec::new();
+
+        add_bot(&state, &mut soldiers, &mut bots, Team::Alpha);
+
+        let mut reached = Vec::new();
+
+        for _ in 0..SECOND * 60 {
+            state.update(&mut soldiers, &mut bots, &mut emitter);
+
+            if let Some(i) = bots[0].last_waypoint {
+                if reached.last() != Some(&i) {
+                    reached.push(i);
+                }
+            }
+
+            if state.round.team_scores[Team::Alpha.index()] > 0 {
+                break;
+            }
+        }
+
+        assert!(reached.len() > 3, "reached waypoints {:?}", reached);
+        assert_eq!(state.round.team_scores[Team::Alpha.index()], 1);
+    }
+
+    #[test]
+    fn bots_fire_at_enemies_in_sight() {
+        let mut state = test_state(GameMode::Deathmatch);
+        let mut soldiers = Vec::new();
+        let mut bots = Vec::new();
+        let mut emitter = Vec::new();
+
+        add_bot(&state, &mut soldiers, &mut bots, Team::None);
+        add_bot(&state, &mut soldiers, &mut bots, Team::None);
+        face_off(&state, &mut soldiers);
+
+        let mut fired = false;
+
+        // stop at the first shot, before anyone dies and the targets are dropped
+        for _ in 0..SECOND * 5 {
+            state.update(&mut soldiers, &mut bots, &mut emitter);
+            fired = !state.bullets.is_empty();
+
+            if fired {
+                break;
+            }
+        }
+
+        assert!(fired);
+        assert!(bots.iter().any(|bot| bot.target.is_some()));
+    }
+
+    #[test]
+    fn bots_score_kills() {
+        let mut state = test_state(GameMode::Deathmatch);
+        let mut soldiers = Vec::new();
+        let mut bots = Vec::new();
+        let mut emitter = Vec::new();
+
+        for _ in 0..4 {
+            add_bot(&state, &mut soldiers, &mut bots, Team::None);
+        }
+
+        face_off(&state, &mut soldiers);
+
+        for _ in 0..SECOND * 60 {
+            state.update(&mut soldiers, &mut bots, &mut emitter);
+        }
+
+        assert!(soldiers.iter().any(|s| s.kills > 0 && s.score > 0));
+    }
+}
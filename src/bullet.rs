@@ -1,5 +1,13 @@
 use super::*;
 
+const SECOND: i16 = 60;
+const ARROW_STUCK_TIME: i16 = SECOND * 10;
+const FLAME_TIME: i16 = SECOND * 3;
+pub const HIT_RADIUS: f32 = 10.0;
+const HIT_IMPACT: f32 = 0.1;
+pub const HEADSHOT_RADIUS: f32 = 5.0;
+const EXPLOSION_RADIUS: f32 = 60.0;
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum BulletStyle {
     Bullet = 1,
@@ -27,15 +35,27 @@ pub struct BulletParams {
     pub timeout: i16,
     pub hit_multiply: f32,
     pub team: Team,
+    pub owner: usize,
     pub sprite: Option<gfx::Weapon>,
 }
 
+#[derive(Debug, Copy, Clone)]
+pub struct ExplosionParams {
+    pub position: Vec2,
+    pub radius: f32,
+    pub power: f32,
+    pub owner: usize,
+    pub team: Team,
+    pub weapon: WeaponKind,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct Bullet {
     pub active: bool,
     pub style: BulletStyle,
     pub weapon: WeaponKind,
     pub team: Team,
+    pub owner: usize,
     pub particle: Particle,
     pub initial_pos: Vec2,
     pub velocity_prev: Vec2,
@@ -45,6 +65,7 @@ pub struct Bullet {
     pub hit_multiply: f32,
     pub hit_multiply_prev: f32,
     pub degrade_count: usize,
+    pub stuck: bool,
     pub sprite: Option<gfx::Weapon>,
 }
 
@@ -54,6 +75,19 @@ impl Default for BulletStyle {
     }
 }
 
+impl BulletStyle {
+    pub fn explosive(&self) -> bool {
+        matches!(
+            *self,
+            BulletStyle::M79Grenade
+                | BulletStyle::LAWMissile
+                | BulletStyle::FragGrenade
+                | BulletStyle::ClusterGrenade
+                | BulletStyle::Cluster
+        )
+    }
+}
+
 impl Bullet {
     pub fn new(params: &BulletParams) -> Bullet {
         let particle = Particle {
@@ -63,7 +97,10 @@ impl Bullet {
             velocity: params.velocity,
             one_over_mass: 1.0,
             timestep: 1.0,
-            gravity: 0.06 * 2.25,
+            gravity: match params.style {
+                BulletStyle::Arrow | BulletStyle::FlameArrow => 0.06 * 4.0,
+                _ => 0.06 * 2.25,
+            },
             e_damping: 0.99,
             ..Default::default()
         };
@@ -73,6 +110,7 @@ impl Bullet {
             style: params.style,
             weapon: params.weapon,
             team: params.team,
+            owner: params.owner,
             particle,
             initial_pos: params.position,
             velocity_prev: params.velocity,
@@ -82,17 +120,30 @@ impl Bullet {
             hit_multiply: params.hit_multiply,
             hit_multiply_prev: params.hit_multiply,
             degrade_count: 0,
+            stuck: false,
             sprite: params.sprite,
         }
     }
 
-    pub fn update(&mut self, map: &MapFile) {
+    // Returns where the bullet hit the map this tick, if it did.
+    pub fn update(
+        &mut self,
+        map: &MapFile,
+        realistic: bool,
+        emitter: &mut Vec<EmitterItem>,
+    ) -> Option<Vec2> {
+        let mut impact = None;
+
         self.velocity_prev = self.particle.velocity;
-        self.particle.euler();
 
-        if let Some((pos, _poly)) = self.map_collision(map) {
-            self.particle.pos = pos;
-            self.active = false;
+        if !self.stuck {
+            self.particle.euler();
+
+            if let Some((pos, _poly)) = self.map_collision(map) {
+                self.particle.pos = pos;
+                self.hit_map(realistic, emitter);
+                impact = Some(pos);
+            }
         }
 
         self.timeout_prev = self.timeout;
@@ -120,11 +171,138 @@ impl Bullet {
             }
         }
 
-        let (x, y) = self.particle.pos.into();
+        if map.out_of_bounds(self.particle.pos) {
+            self.active = false;
+        }
+
+        impact
+    }
+
+    fn hit_map(&mut self, realistic: bool, emitter: &mut Vec<EmitterItem>) {
+        let pos = self.particle.pos;
+
+        match self.style {
+            style if style.explosive() => self.explode(emitter),
+            BulletStyle::Arrow | BulletStyle::FlameArrow | BulletStyle::Flame => {
+                self.stuck = true;
+                self.particle.old_pos = pos;
+                self.timeout = iif!(
+                    self.style == BulletStyle::Flame,
+                    FLAME_TIME,
+                    ARROW_STUCK_TIME
+                );
+                self.timeout_prev = self.timeout;
 
-        if f32::max(x.abs(), y.abs()) > (map.sectors_num * map.sectors_division - 10) as f32 {
+                if self.style == BulletStyle::FlameArrow {
+                    emitter.push(EmitterItem::Bullet(BulletParams {
+                        style: BulletStyle::Flame,
+                        weapon: self.weapon,
+                        position: pos - vec2normalize(self.particle.velocity) * 2.0,
+                        velocity: Vec2::zero(),
+                        timeout: FLAME_TIME,
+                        hit_multiply: 0.0,
+                        team: self.team,
+                        owner: self.owner,
+                        sprite: None,
+                    }));
+                }
+            }
+            BulletStyle::ThrownKnife => {
+                self.active = false;
+
+                emitter.push(EmitterItem::Thing(ThingParams {
+                    kind: ThingKind::Weapon(Weapon::new(WeaponKind::Knife, realistic)),
+                    position: pos,
+                    velocity: Vec2::zero(),
+                    rotation: vec2angle(self.particle.velocity).0,
+                    owner: self.owner,
+                    stuck: true,
+                }));
+            }
+            _ => self.active = false,
+        }
+    }
+
+    fn explode(&mut self, emitter: &mut Vec<EmitterItem>) {
+        self.active = false;
+
+        emitter.push(EmitterItem::Explosion(ExplosionParams {
+            position: self.particle.pos,
+            radius: EXPLOSION_RADIUS,
+            power: self.hit_multiply,
+            owner: self.owner,
+            team: self.team,
+            weapon: self.weapon,
+        }));
+    }
+
+    // Bullets also hit dead bodies, which is how heavy weapons gib ragdolls.
+    pub fn check_soldier_collision(
+        &mut self,
+        soldier: &mut Soldier,
+        friendly_fire: bool,
+        emitter: &mut Vec<EmitterItem>,
+    ) -> bool {
+        if !self.active || !soldier.active || self.owner == soldier.num {
+            return false;
+        }
+
+        if !friendly_fire && !soldier.dead_meat && soldier.is_teammate(self.team, self.owner) {
+            return false;
+        }
+
+        // stuck arrows are harmless, but fire left on the ground keeps burning whoever
+        // walks in
+        if self.stuck && self.style != BulletStyle::Flame {
+            return false;
+        }
+
+        if soldier.dead_meat && self.style == BulletStyle::Flame {
+            return false;
+        }
+
+        let a = self.particle.old_pos;
+        let b = self.particle.pos;
+        let center = soldier.body_center();
+        let steps = i32::max(1, f32::ceil(distance(a, b) / 2.5) as i32);
+
+        let hit = (0..steps + 1)
+            .map(|i| lerp(a, b, i as f32 / steps as f32))
+            .find(|p| distance(*p, center) < HIT_RADIUS);
+
+        let hit = match hit {
+            Some(hit) => hit,
+            None => return false,
+        };
+
+        if self.style.explosive() {
+            self.explode(emitter);
+            return true;
+        }
+
+        if self.style != BulletStyle::Flame {
+            if distance(hit, soldier.skeleton.pos(12)) < HEADSHOT_RADIUS {
+                soldier.knock_off_helmet(self.particle.velocity * HIT_IMPACT, emitter);
+            }
+
+            let damage = self.hit_multiply * vec2length(self.particle.velocity);
+            soldier.hit(
+                damage,
+                self.particle.pos,
+                self.particle.velocity * HIT_IMPACT,
+                self.owner,
+                self.weapon,
+            );
             self.active = false;
         }
+
+        if !soldier.dead_meat
+            && (self.style == BulletStyle::Flame || self.style == BulletStyle::FlameArrow)
+        {
+            soldier.ignite(Some((self.owner, self.weapon)));
+        }
+
+        true
     }
 
     pub fn map_collision(&self, map: &MapFile) -> Option<(Vec2, usize)> {
@@ -0,0 +1,284 @@
+use super::*;
+use glutin::{ElementState, KeyboardInput, VirtualKeyCode};
+
+const SECOND: i32 = 60;
+const MAX_LENGTH: usize = 78;
+const MAX_LOG: usize = 64;
+const FLOOD_MESSAGES: usize = 5;
+const FLOOD_TIME: i32 = SECOND * 4;
+const MUTE_TIME: i32 = SECOND * 10;
+pub const MESSAGE_TIME: i32 = SECOND * 8;
+pub const BUBBLE_TIME: i32 = SECOND * 4;
+
+// Soldat's radio calls, picked by number from the radio menu.
+pub static RADIO: &[&str] = &[
+    "Attack!",
+    "Defend the flag!",
+    "Get the enemy flag!",
+    "Return our flag!",
+    "Need backup!",
+    "Follow me!",
+    "Enemy spotted!",
+    "Affirmative",
+    "Negative",
+];
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ChatKind {
+    All,
+    Team,
+    Radio,
+    Server,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChatMessage {
+    pub sender: Option<usize>,
+    pub name: String,
+    pub team: Team,
+    pub kind: ChatKind,
+    pub text: String,
+    pub time: i32,
+    pub recipient: Option<usize>,
+}
+
+// Runs on every message before it's shown, may change the text and returns false to
+// drop the message.
+pub type ChatHook = fn(&mut ChatMessage) -> bool;
+
+// All and team chat plus radio calls. Messages go through `send`, which is where a
+// server applies flood protection and its hooks, and end up in the log and in bubbles
+// over the sender's head.
+pub struct Chat {
+    pub log: Vec<ChatMessage>,
+    pub input: Option<(ChatKind, String)>,
+    pub radio_menu: bool,
+    hooks: Vec<ChatHook>,
+    recent: Vec<(usize, i32)>,
+    muted: Vec<(usize, i32)>,
+    skip_char: bool,
+    time: i32,
+    pushed: usize,
+}
+
+impl ChatMessage {
+    // Team messages are only for the sender's team, messages with a recipient only for
+    // that soldier.
+    pub fn visible_to(&self, soldier: &Soldier) -> bool {
+        if let Some(num) = self.recipient {
+            return num == soldier.num;
+        }
+
+        match self.kind {
+            ChatKind::All | ChatKind::Server => true,
+            ChatKind::Team | ChatKind::Radio => self.team == soldier.team,
+        }
+    }
+}
+
+impl Chat {
+    pub fn new() -> Chat {
+        Chat {
+            log: Vec::new(),
+            input: None,
+            radio_menu: false,
+            hooks: Vec::new(),
+            recent: Vec::new(),
+            muted: Vec::new(),
+            skip_char: false,
+            time: 0,
+            pushed: 0,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn add_hook(&mut self, hook: ChatHook) {
+        self.hooks.push(hook);
+    }
+
+    pub fn time(&self) -> i32 {
+        self.time
+    }
+
+    // How many messages made it into the log so far, old ones included.
+    pub fn pushed(&self) -> usize {
+        self.pushed
+    }
+
+    pub fn update(&mut self) {
+        self.time += 1;
+
+        let time = self.time;
+        self.recent.retain(|&(_, t)| time - t < FLOOD_TIME);
+        self.muted.retain(|&(_, until)| until > time);
+    }
+
+    // Senders going over FLOOD_MESSAGES within FLOOD_TIME get muted for a while.
+    pub fn send(&mut self, soldiers: &[Soldier], sender: usize, kind: ChatKind, text: &str) {
+        let soldier = match soldiers.iter().find(|s| s.num == sender) {
+            Some(soldier) => soldier,
+            None => return,
+        };
+
+        let text: String = text.trim().chars().take(MAX_LENGTH).collect();
+
+        if text.is_empty() {
+            return;
+        }
+
+        if self.muted.iter().any(|&(num, _)| num == sender) {
+            self.notice_to(sender, "You are muted for flooding");
+            return;
+        }
+
+        self.recent.push((sender, self.time));
+        let sent = self
+            .recent
+            .iter()
+            .filter(|&&(num, _)| num == sender)
+            .count();
+
+        if sent > FLOOD_MESSAGES {
+            self.muted.push((sender, self.time + MUTE_TIME));
+            self.notice(&format!("{} is muted for flooding", soldier.player.name));
+            return;
+        }
+
+        // without teams team chat goes to everyone
+        let kind = iif!(
+            soldier.team == Team::None && kind == ChatKind::Team,
+            ChatKind::All,
+            kind
+        );
+
+        let mut message = ChatMessage {
+            sender: Some(sender),
+            name: soldier.player.name.clone(),
+            team: soldier.team,
+            kind,
+            text,
+            time: self.time,
+            recipient: None,
+        };
+
+        if self.hooks.iter().all(|hook| hook(&mut message)) {
+            self.push(message);
+        }
+    }
+
+    pub fn notice(&mut self, text: &str) {
+        self.push_notice(None, text);
+    }
+
+    // A notice only the given soldier gets to see.
+    pub fn notice_to(&mut self, recipient: usize, text: &str) {
+        self.push_notice(Some(recipient), text);
+    }
+
+    // The last message a soldier sent if it's recent enough for a bubble.
+    pub fn bubble(&self, num: usize) -> Option<&ChatMessage> {
+        self.log
+            .iter()
+            .rev()
+            .take_while(|m| self.time - m.time < BUBBLE_TIME)
+            .find(|m| m.sender == Some(num) && m.kind != ChatKind::Radio)
+    }
+
+    // Radio calls are picked from a menu instead of typed.
+    pub fn open(&mut self, kind: ChatKind) {
+        if kind == ChatKind::Radio {
+            self.radio_menu = true;
+            return;
+        }
+
+        self.input = Some((kind, String::new()));
+        self.radio_menu = false;
+        self.skip_char = true;
+    }
+
+    pub fn typing(&self) -> bool {
+        self.input.is_some()
+    }
+
+    pub fn key_input(&mut self, input: &KeyboardInput, soldiers: &[Soldier]) {
+        if input.state != ElementState::Pressed {
+            return;
+        }
+
+        match input.virtual_keycode {
+            Some(VirtualKeyCode::Escape) => self.input = None,
+            Some(VirtualKeyCode::Back) => {
+                if let Some((_, ref mut text)) = self.input {
+                    text.pop();
+                }
+            }
+            Some(VirtualKeyCode::Return) | Some(VirtualKeyCode::NumpadEnter) => {
+                if let Some((kind, text)) = self.input.take() {
+                    self.send(soldiers, soldiers[0].num, kind, &text);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    pub fn char_input(&mut self, c: char) {
+        // the key that opened the chat shows up here as well
+        if std::mem::take(&mut self.skip_char) {
+            return;
+        }
+
+        if let Some((_, ref mut text)) = self.input {
+            if !c.is_control() && text.chars().count() < MAX_LENGTH {
+                text.push(c);
+            }
+        }
+    }
+
+    // Number keys pick a radio call while the menu is open, anything else closes it.
+    pub fn radio_input(&mut self, input: &KeyboardInput, soldiers: &[Soldier]) {
+        if input.state != ElementState::Pressed {
+            return;
+        }
+
+        let keys = [
+            VirtualKeyCode::Key1,
+            VirtualKeyCode::Key2,
+            VirtualKeyCode::Key3,
+            VirtualKeyCode::Key4,
+            VirtualKeyCode::Key5,
+            VirtualKeyCode::Key6,
+            VirtualKeyCode::Key7,
+            VirtualKeyCode::Key8,
+            VirtualKeyCode::Key9,
+        ];
+
+        if let Some(i) = keys.iter().position(|k| input.virtual_keycode == Some(*k)) {
+            self.send(soldiers, soldiers[0].num, ChatKind::Radio, RADIO[i]);
+        }
+
+        self.radio_menu = false;
+    }
+
+    fn push_notice(&mut self, recipient: Option<usize>, text: &str) {
+        let message = ChatMessage {
+            sender: None,
+            name: String::new(),
+            team: Team::None,
+            kind: ChatKind::Server,
+            text: text.to_owned(),
+            time: self.time,
+            recipient,
+        };
+
+        self.push(message);
+    }
+
+    fn push(&mut self, message: ChatMessage) {
+        self.log.push(message);
+        self.pushed += 1;
+
+        if self.log.len() > MAX_LOG {
+            self.log.remove(0);
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use super::*;
+use ini::Ini;
+
+#[derive(Debug, Copy, Clone)]
+pub struct BarrettConfig {
+    pub stance_penalty: bool,
+    pub scope: bool,
+}
+
+pub struct ServerConfig {
+    pub realistic_mode: bool,
+    barrett: [BarrettConfig; 7],
+    pub bonus_frequency: u8,
+    pub game_mode: GameMode,
+    pub score_limit: Option<i32>,
+    pub time_limit: Option<i32>,
+    pub friendly_fire: bool,
+    pub bots: usize,
+    pub bot_difficulty: BotDifficulty,
+    pub weapon_limits: WeaponLimits,
+}
+
+impl Default for ServerConfig {
+    fn default() -> ServerConfig {
+        ServerConfig {
+            realistic_mode: false,
+            barrett: [BarrettConfig {
+                stance_penalty: true,
+                scope: true,
+            }; 7],
+            bonus_frequency: 2,
+            game_mode: GameMode::Deathmatch,
+            score_limit: None,
+            time_limit: Some(15),
+            friendly_fire: false,
+            bots: 0,
+            bot_difficulty: BotDifficulty::Normal,
+            weapon_limits: WeaponLimits::new(),
+        }
+    }
+}
+
+impl ServerConfig {
+    // Barrett rules of the configured game mode.
+    pub fn barrett(&self) -> BarrettConfig {
+        self.barrett[self.game_mode.index()]
+    }
+
+    pub fn load_from_file(file_name: &str) -> ServerConfig {
+        let mut config = ServerConfig::default();
+
+        if let Ok(cfg) = Ini::load_from_file(file_name) {
+            config.load_data(&cfg);
+            config.weapon_limits.load_data(&cfg);
+        }
+
+        config
+    }
+
+    pub fn load_data(&mut self, cfg: &Ini) {
+        if let Some(data) = cfg.section(Some("GAME".to_owned())) {
+            if let Some(value) = data.get("RealisticMode") {
+                self.realistic_mode = parse_bool(value);
+            }
+
+            // 0 turns bonuses off, higher values bring them in more often
+            if let Some(value) = data.get("BonusFrequency") {
+                if let Ok(frequency) = value.trim().parse::<u8>() {
+                    self.bonus_frequency = u8::min(frequency, 5);
+                }
+            }
+
+            if let Some(mode) = data.get("GameMode").and_then(|v| GameMode::from_name(v)) {
+                self.game_mode = mode;
+            }
+
+            // 0 or less leaves the mode's default limit
+            if let Some(Ok(limit)) = data.get("ScoreLimit").map(|v| v.trim().parse::<i32>()) {
+                self.score_limit = iif!(limit > 0, Some(limit), None);
+            }
+
+            // in minutes, 0 plays without a time limit
+            if let Some(Ok(limit)) = data.get("TimeLimit").map(|v| v.trim().parse::<i32>()) {
+                self.time_limit = iif!(limit > 0, Some(limit), None);
+            }
+
+            if let Some(value) = data.get("FriendlyFire") {
+                self.friendly_fire = parse_bool(value);
+            }
+
+            if let Some(Ok(bots)) = data.get("Bots").map(|v| v.trim().parse::<usize>()) {
+                self.bots = bots;
+            }
+
+            if let Some(difficulty) = data
+                .get("BotDifficulty")
+                .and_then(|v| BotDifficulty::from_name(v))
+            {
+                self.bot_difficulty = difficulty;
+            }
+        }
+
+        // Barrett keys in [GAME] apply to every mode, a section named after a mode,
+        // e.g. [CTF], overrides them for that mode.
+        for mode in GameMode::values() {
+            let barrett = &mut self.barrett[mode.index()];
+
+            for section in &["GAME", mode.short_name()] {
+                if let Some(data) = cfg.section(Some((*section).to_owned())) {
+                    if let Some(value) = data.get("BarrettStancePenalty") {
+                        barrett.stance_penalty = parse_bool(value);
+                    }
+
+                    if let Some(value) = data.get("BarrettScope") {
+                        barrett.scope = parse_bool(value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn parse_bool(value: &str) -> bool {
+    let value = value.trim();
+    value == "1" || value.eq_ignore_ascii_case("true")
+}
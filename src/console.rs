@@ -0,0 +1,511 @@
+use super::*;
+use glutin::{ElementState, KeyboardInput, VirtualKeyCode};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+const MAX_LINES: usize = 256;
+const MAX_HISTORY: usize = 64;
+const MAX_EXEC_DEPTH: usize = 8;
+
+// Drop-down developer console. Lines are commands or cvar names, a cvar name alone
+// prints its value and followed by a value sets it. Several commands can be put on one
+// line separated by `;`.
+pub struct Console {
+    pub active: bool,
+    pub input: String,
+    pub lines: Vec<String>,
+    pub requested_map: Option<String>,
+    history: Vec<String>,
+    history_pos: usize,
+    exec_depth: usize,
+}
+
+// The parts of the game console commands get to change.
+pub struct ConsoleEnv<'a> {
+    pub state: &'a mut MainState,
+    pub soldiers: &'a mut Vec<Soldier>,
+    pub emitter: &'a mut Vec<EmitterItem>,
+}
+
+enum CvarValue<'a> {
+    Bool(&'a mut bool),
+    Float(&'a mut f32),
+}
+
+struct Cvar {
+    name: &'static str,
+    help: &'static str,
+    min: f32,
+    max: f32,
+    value: for<'a, 'b> fn(&'a mut ConsoleEnv<'b>) -> CvarValue<'a>,
+}
+
+struct Command {
+    name: &'static str,
+    usage: &'static str,
+    help: &'static str,
+    run: fn(&mut Console, &mut ConsoleEnv, &[&str]) -> Result<(), String>,
+}
+
+static CVARS: &[Cvar] = &[
+    Cvar {
+        name: "sv_gravity",
+        help: "gravity pulling soldiers down every tick",
+        min: -1.0,
+        max: 1.0,
+        value: |env| CvarValue::Float(&mut env.state.gravity),
+    },
+    Cvar {
+        name: "sv_friendlyfire",
+        help: "whether teammates can hurt each other",
+        min: 0.0,
+        max: 1.0,
+        value: |env| CvarValue::Bool(&mut env.state.config.friendly_fire),
+    },
+    Cvar {
+        name: "cl_zoom",
+        help: "camera zoom, 0 is the default view, negative values zoom in",
+        min: -3.0,
+        max: 3.0,
+        value: |env| CvarValue::Float(&mut env.state.zoom),
+    },
+    Cvar {
+        name: "r_skeleton",
+        help: "draw soldier skeletons",
+        min: 0.0,
+        max: 1.0,
+        value: |env| CvarValue::Bool(&mut env.state.debug.skeleton),
+    },
+    Cvar {
+        name: "r_polygons",
+        help: "draw polygon outlines coloured by type",
+        min: 0.0,
+        max: 1.0,
+        value: |env| CvarValue::Bool(&mut env.state.debug.polygons),
+    },
+    Cvar {
+        name: "r_sectors",
+        help: "draw the sector grid and the polygons of the player's sector",
+        min: 0.0,
+        max: 1.0,
+        value: |env| CvarValue::Bool(&mut env.state.debug.sectors),
+    },
+    Cvar {
+        name: "r_bullets",
+        help: "draw bullet paths and impact points",
+        min: 0.0,
+        max: 1.0,
+        value: |env| CvarValue::Bool(&mut env.state.debug.bullets),
+    },
+    Cvar {
+        name: "r_colliders",
+        help: "draw colliders and spawnpoints",
+        min: 0.0,
+        max: 1.0,
+        value: |env| CvarValue::Bool(&mut env.state.debug.colliders),
+    },
+    Cvar {
+        name: "r_waypoints",
+        help: "draw the waypoint graph",
+        min: 0.0,
+        max: 1.0,
+        value: |env| CvarValue::Bool(&mut env.state.debug.waypoints),
+    },
+    Cvar {
+        name: "r_routes",
+        help: "draw the waypoint route from the player to the cursor",
+        min: 0.0,
+        max: 1.0,
+        value: |env| CvarValue::Bool(&mut env.state.debug.routes),
+    },
+    Cvar {
+        name: "r_hitzones",
+        help: "draw the areas bullets hit soldiers in",
+        min: 0.0,
+        max: 1.0,
+        value: |env| CvarValue::Bool(&mut env.state.debug.hit_zones),
+    },
+    Cvar {
+        name: "timescale",
+        help: "game speed, 1 is normal",
+        min: 0.0,
+        max: 10.0,
+        value: |env| CvarValue::Float(&mut env.state.timescale),
+    },
+];
+
+static COMMANDS: &[Command] = &[
+    Command {
+        name: "help",
+        usage: "help",
+        help: "list commands and cvars",
+        run: cmd_help,
+    },
+    Command {
+        name: "echo",
+        usage: "echo <text>",
+        help: "print text to the console",
+        run: cmd_echo,
+    },
+    Command {
+        name: "clear",
+        usage: "clear",
+        help: "clear the console",
+        run: cmd_clear,
+    },
+    Command {
+        name: "exec",
+        usage: "exec <file>",
+        help: "run the commands in a file",
+        run: cmd_exec,
+    },
+    Command {
+        name: "map",
+        usage: "map <name>",
+        help: "change the map",
+        run: cmd_map,
+    },
+    Command {
+        name: "savewaypoints",
+        usage: "savewaypoints",
+        help: "write the map's waypoints, generated ones included, into its file",
+        run: cmd_savewaypoints,
+    },
+    Command {
+        name: "kill",
+        usage: "kill",
+        help: "kill the local player",
+        run: cmd_kill,
+    },
+    Command {
+        name: "give",
+        usage: "give <weapon>",
+        help: "replace the active weapon, e.g. give ak74",
+        run: cmd_give,
+    },
+    Command {
+        name: "say",
+        usage: "say <text>",
+        help: "send a chat message to everyone",
+        run: cmd_say,
+    },
+    Command {
+        name: "say_team",
+        usage: "say_team <text>",
+        help: "send a chat message to your team",
+        run: cmd_say_team,
+    },
+    Command {
+        name: "radio",
+        usage: "radio <number>",
+        help: "send a radio call to your team, radio alone lists them",
+        run: cmd_radio,
+    },
+    Command {
+        name: "nextweapon",
+        usage: "nextweapon",
+        help: "replace the active weapon with the next one in the list",
+        run: cmd_nextweapon,
+    },
+];
+
+impl Console {
+    pub fn new() -> Console {
+        Console {
+            active: false,
+            input: String::new(),
+            lines: Vec::new(),
+            requested_map: None,
+            history: Vec::new(),
+            history_pos: 0,
+            exec_depth: 0,
+        }
+    }
+
+    pub fn print(&mut self, line: &str) {
+        self.lines.push(line.to_owned());
+
+        if self.lines.len() > MAX_LINES {
+            self.lines.remove(0);
+        }
+    }
+
+    pub fn execute(&mut self, env: &mut ConsoleEnv, line: &str) {
+        for command in line.split(';') {
+            let args: Vec<&str> = command.split_whitespace().collect();
+
+            if args.is_empty() {
+                continue;
+            }
+
+            if let Err(error) = self.run(env, &args) {
+                self.print(&error);
+            }
+        }
+    }
+
+    // Runs a file line by line, `//` starts a comment. Returns false when the file
+    // can't be read.
+    pub fn exec_file(&mut self, env: &mut ConsoleEnv, file_name: &str) -> bool {
+        let file = match File::open(file_name) {
+            Ok(file) => file,
+            Err(_) => return false,
+        };
+
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
+            let line = line.split("//").next().unwrap_or("");
+            self.execute(env, line);
+        }
+
+        true
+    }
+
+    pub fn key_input(&mut self, input: &KeyboardInput, env: &mut ConsoleEnv) {
+        if input.state != ElementState::Pressed {
+            return;
+        }
+
+        match input.virtual_keycode {
+            Some(VirtualKeyCode::Escape) => self.active = false,
+            Some(VirtualKeyCode::Back) => {
+                self.input.pop();
+            }
+            Some(VirtualKeyCode::Return) | Some(VirtualKeyCode::NumpadEnter) => {
+                let line = std::mem::take(&mut self.input);
+                self.print(&format!("] {}", line));
+
+                if !line.trim().is_empty() && self.history.last() != Some(&line) {
+                    self.history.push(line.clone());
+
+                    if self.history.len() > MAX_HISTORY {
+                        self.history.remove(0);
+                    }
+                }
+
+                self.history_pos = self.history.len();
+                self.execute(env, &line);
+            }
+            Some(VirtualKeyCode::Up) if self.history_pos > 0 => {
+                self.history_pos -= 1;
+                self.input = self.history[self.history_pos].clone();
+            }
+            Some(VirtualKeyCode::Down) if self.history_pos < self.history.len() => {
+                self.history_pos += 1;
+                self.input = self
+                    .history
+                    .get(self.history_pos)
+                    .cloned()
+                    .unwrap_or_default();
+            }
+            _ => (),
+        }
+    }
+
+    pub fn char_input(&mut self, c: char) {
+        // the toggle key shows up here as well
+        if !c.is_control() && c != '`' && c != '~' {
+            self.input.push(c);
+        }
+    }
+
+    fn run(&mut self, env: &mut ConsoleEnv, args: &[&str]) -> Result<(), String> {
+        let name = args[0].to_lowercase();
+
+        if let Some(command) = COMMANDS.iter().find(|c| c.name == name) {
+            return (command.run)(self, env, &args[1..]);
+        }
+
+        let cvar = CVARS
+            .iter()
+            .find(|c| c.name == name)
+            .ok_or_else(|| format!("unknown command: {}", args[0]))?;
+
+        let value = (cvar.value)(env);
+
+        match (value, args.get(1)) {
+            (CvarValue::Bool(value), None) => {
+                self.print(&format!("{} is {}", cvar.name, *value as i32))
+            }
+            (CvarValue::Float(value), None) => self.print(&format!("{} is {}", cvar.name, value)),
+            (CvarValue::Bool(value), Some(arg)) => {
+                *value = match *arg {
+                    "1" | "true" | "on" => true,
+                    "0" | "false" | "off" => false,
+                    _ => return Err(format!("{} takes 0 or 1", cvar.name)),
+                }
+            }
+            (CvarValue::Float(value), Some(arg)) => match arg.parse::<f32>() {
+                Ok(v) if (cvar.min..=cvar.max).contains(&v) => *value = v,
+                _ => {
+                    return Err(format!(
+                        "{} takes a number from {} to {}",
+                        cvar.name, cvar.min, cvar.max
+                    ))
+                }
+            },
+        }
+
+        Ok(())
+    }
+}
+
+fn cmd_help(console: &mut Console, _env: &mut ConsoleEnv, _args: &[&str]) -> Result<(), String> {
+    for command in COMMANDS {
+        console.print(&format!("{:<16} {}", command.usage, command.help));
+    }
+
+    for cvar in CVARS {
+        console.print(&format!("{:<16} {}", cvar.name, cvar.help));
+    }
+
+    Ok(())
+}
+
+fn cmd_echo(console: &mut Console, _env: &mut ConsoleEnv, args: &[&str]) -> Result<(), String> {
+    console.print(&args.join(" "));
+    Ok(())
+}
+
+fn cmd_clear(console: &mut Console, _env: &mut ConsoleEnv, _args: &[&str]) -> Result<(), String> {
+    console.lines.clear();
+    Ok(())
+}
+
+fn cmd_exec(console: &mut Console, env: &mut ConsoleEnv, args: &[&str]) -> Result<(), String> {
+    let file_name = args.first().ok_or("usage: exec <file>")?;
+
+    // files that exec each other would never finish
+    if console.exec_depth >= MAX_EXEC_DEPTH {
+        return Err(format!("can't exec {}, too many nested execs", file_name));
+    }
+
+    console.exec_depth += 1;
+    let found = console.exec_file(env, file_name);
+    console.exec_depth -= 1;
+
+    if found {
+        Ok(())
+    } else {
+        Err(format!("can't read {}", file_name))
+    }
+}
+
+fn cmd_map(console: &mut Console, _env: &mut ConsoleEnv, args: &[&str]) -> Result<(), String> {
+    let name = args.first().ok_or("usage: map <name>")?;
+
+    if !Path::new("assets/maps/")
+        .join(format!("{}.pms", name))
+        .exists()
+    {
+        return Err(format!("map {} not found", name));
+    }
+
+    // the map is swapped between frames, graphics have to be reloaded along with it
+    console.requested_map = Some((*name).to_owned());
+    Ok(())
+}
+
+fn cmd_savewaypoints(
+    console: &mut Console,
+    env: &mut ConsoleEnv,
+    _args: &[&str],
+) -> Result<(), String> {
+    let map = &env.state.map;
+
+    map.save_waypoints()
+        .map_err(|err| format!("can't save {}: {}", map.filename, err))?;
+
+    console.print(&format!(
+        "saved {} waypoints to {}",
+        map.waypoints.len(),
+        map.filename
+    ));
+    Ok(())
+}
+
+fn cmd_kill(_console: &mut Console, env: &mut ConsoleEnv, _args: &[&str]) -> Result<(), String> {
+    env.soldiers[0].die(env.emitter, env.state.realistic_mode);
+    Ok(())
+}
+
+fn cmd_give(_console: &mut Console, env: &mut ConsoleEnv, args: &[&str]) -> Result<(), String> {
+    let simplify = |name: &str| {
+        name.chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect::<String>()
+            .to_lowercase()
+    };
+
+    let name = simplify(&args.join(""));
+    let realistic = env.state.realistic_mode;
+
+    let kind = WeaponKind::values()
+        .iter()
+        .cloned()
+        .find(|kind| {
+            let weapon = Weapon::new(*kind, realistic);
+            name == simplify(&format!("{:?}", kind))
+                || name == simplify(weapon.name)
+                || name == simplify(weapon.ini_name)
+        })
+        .ok_or_else(|| format!("unknown weapon: {}", args.join(" ")))?;
+
+    let soldier = &mut env.soldiers[0];
+    soldier.weapons[soldier.active_weapon] = Weapon::new(kind, realistic);
+    Ok(())
+}
+
+fn cmd_nextweapon(
+    _console: &mut Console,
+    env: &mut ConsoleEnv,
+    _args: &[&str],
+) -> Result<(), String> {
+    let soldier = &mut env.soldiers[0];
+    let index = soldier.primary_weapon().kind.index();
+    let index = (index + 1) % (WeaponKind::NoWeapon.index() + 1);
+    let kind = WeaponKind::values()[index];
+    soldier.weapons[soldier.active_weapon] = Weapon::new(kind, env.state.realistic_mode);
+    Ok(())
+}
+
+fn cmd_say(_console: &mut Console, env: &mut ConsoleEnv, args: &[&str]) -> Result<(), String> {
+    let num = env.soldiers[0].num;
+    env.state
+        .chat
+        .send(env.soldiers, num, ChatKind::All, &args.join(" "));
+    Ok(())
+}
+
+fn cmd_say_team(_console: &mut Console, env: &mut ConsoleEnv, args: &[&str]) -> Result<(), String> {
+    let num = env.soldiers[0].num;
+    env.state
+        .chat
+        .send(env.soldiers, num, ChatKind::Team, &args.join(" "));
+    Ok(())
+}
+
+fn cmd_radio(console: &mut Console, env: &mut ConsoleEnv, args: &[&str]) -> Result<(), String> {
+    let arg = match args.first() {
+        Some(arg) => arg,
+        None => {
+            for (i, call) in RADIO.iter().enumerate() {
+                console.print(&format!("{} {}", i + 1, call));
+            }
+
+            return Ok(());
+        }
+    };
+
+    let call = arg
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| RADIO.get(n.wrapping_sub(1)))
+        .ok_or_else(|| format!("radio takes a number from 1 to {}", RADIO.len()))?;
+
+    let num = env.soldiers[0].num;
+    env.state
+        .chat
+        .send(env.soldiers, num, ChatKind::Radio, call);
+    Ok(())
+}
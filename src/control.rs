@@ -4,19 +4,42 @@ const POS_STAND: u8 = 1;
 const POS_CROUCH: u8 = 2;
 const POS_PRONE: u8 = 3;
 
-const RUNSPEED: f32 = 0.118;
+pub const RUNSPEED: f32 = 0.118;
 const RUNSPEEDUP: f32 = RUNSPEED / 6.0;
 const FLYSPEED: f32 = 0.03;
-const JUMPSPEED: f32 = 0.66;
+pub const JUMPSPEED: f32 = 0.66;
 const CROUCHRUNSPEED: f32 = RUNSPEED / 0.6;
 const PRONESPEED: f32 = RUNSPEED * 4.0;
 const ROLLSPEED: f32 = RUNSPEED / 1.2;
 const JUMPDIRSPEED: f32 = 0.30;
-const JETSPEED: f32 = 0.10;
+pub const JETSPEED: f32 = 0.10;
 const SECOND: i32 = 60;
 
 const DEFAULT_IDLETIME: i32 = SECOND * 8;
 
+// Everything a player can do with the soldier plus opening the chat, bindings map
+// inputs to these.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Action {
+    Left,
+    Right,
+    Jump,
+    Crouch,
+    Prone,
+    Fire,
+    Jet,
+    Grenade,
+    ChangeWeapon,
+    ThrowWeapon,
+    DropWeapon,
+    Reload,
+    FlagThrow,
+    Use,
+    Chat,
+    TeamChat,
+    Radio,
+}
+
 #[derive(Default, Debug)]
 pub struct Control {
     pub left: bool,
@@ -32,6 +55,7 @@ pub struct Control {
     pub reload: bool,
     pub prone: bool,
     pub flag_throw: bool,
+    pub use_item: bool,
     pub mouse_aim_x: i32,
     pub mouse_aim_y: i32,
     pub mouse_dist: i32,
@@ -55,11 +79,6 @@ impl Soldier {
             self.body_animation.speed = 1;
         }
 
-        self.control.mouse_aim_x =
-            (state.mouse.x - state.game_width as f32 / 2.0 + state.camera.x).round() as i32;
-        self.control.mouse_aim_y =
-            (state.mouse.y - state.game_height as f32 / 2.0 + state.camera.y).round() as i32;
-
         let (mut cleft, mut cright) = (self.control.left, self.control.right);
 
         // If both left and right directions are pressed, then decide which direction to go in
@@ -127,6 +146,12 @@ impl Soldier {
             self.control.free_controls();
         }
 
+        // crawling, no jumping or jetting
+        if self.half_dead {
+            self.control.up = false;
+            self.control.jets = false;
+        }
+
         //self.fired = 0;
         self.control.mouse_aim_x =
             (self.control.mouse_aim_x as f32 + self.particle.velocity.x).round() as i32;
@@ -162,10 +187,12 @@ impl Soldier {
         }
 
         // FIRE!!!!
-        if self.primary_weapon().kind == WeaponKind::Chainsaw
-            || (self.body_animation.id != Anim::Roll) && (self.body_animation.id != Anim::RollBack)
-                && (self.body_animation.id != Anim::Melee)
-                && (self.body_animation.id != Anim::Change)
+        if self.stat_gun.is_none()
+            && (self.primary_weapon().kind == WeaponKind::Chainsaw
+                || (self.body_animation.id != Anim::Roll)
+                    && (self.body_animation.id != Anim::RollBack)
+                    && (self.body_animation.id != Anim::Melee)
+                    && (self.body_animation.id != Anim::Change))
         {
             if ((self.body_animation.id == Anim::HandsUpAim) && (self.body_animation.frame == 11))
                 || (self.body_animation.id != Anim::HandsUpAim)
@@ -177,17 +204,32 @@ impl Soldier {
                         || self.primary_weapon().kind == WeaponKind::Knife
                     {
                         self.body_apply_animation(Anim::Punch, 1);
-                    } else {
-                        self.fire(emitter);
+                    } else if self.primary_weapon().kind == WeaponKind::Barrett
+                        && self.primary_weapon().start_up_time_count > 0
+                    {
+                        self.body_apply_animation(Anim::Barret, 1);
+                        self.weapons[self.active_weapon].start_up_time_count -= 1;
+                    } else if self.body_animation.id != Anim::ReloadBow {
+                        self.fire(state, emitter);
                         self.control.fire = false;
+
+                        if self.holds_bow() {
+                            self.body_apply_animation(Anim::ReloadBow, 1);
+                        }
                     }
+                } else {
+                    let weapon = &mut self.weapons[self.active_weapon];
+                    weapon.start_up_time_count = weapon.start_up_time;
                 }
             }
         }
 
         // change weapon animation
         if (self.body_animation.id != Anim::Roll) && (self.body_animation.id != Anim::RollBack) {
-            if self.control.change {
+            // the rambo can swap between the two bows but not back to a gun
+            let bows = [WeaponKind::Bow, WeaponKind::FlameBow];
+
+            if self.control.change && (!self.holds_bow() || self.secondary_weapon().is_any(&bows)) {
                 self.body_apply_animation(Anim::Change, 1);
             }
         }
@@ -207,10 +249,10 @@ impl Soldier {
         }
 
         // throw weapon
-        if self.control.drop
+        if (self.control.drop || self.control.throw)
             && (self.body_animation.id != Anim::Change || self.body_animation.frame > 25)
             && !self.body_animation.is_any(&[Anim::Roll, Anim::RollBack, Anim::ThrowWeapon])
-            // && !flamegod bonus
+            && self.bonus != Some(Bonus::Flamegod)
             && !self.primary_weapon().is_any(
                 &[
                     WeaponKind::Bow,
@@ -228,9 +270,9 @@ impl Soldier {
         // throw knife
         if self.body_animation.id == Anim::ThrowWeapon
             && self.primary_weapon().kind == WeaponKind::Knife
-            && (!self.control.drop || self.body_animation.frame == 16)
+            && (!(self.control.drop || self.control.throw) || self.body_animation.frame == 16)
         {
-            let weapon = Weapon::new(WeaponKind::ThrownKnife, false);
+            let weapon = Weapon::new(WeaponKind::ThrownKnife, state.realistic_mode);
             let aim_x = self.control.mouse_aim_x as f32;
             let aim_y = self.control.mouse_aim_y as f32;
             let dir = vec2normalize(vec2(aim_x, aim_y) - self.skeleton.pos(15));
@@ -247,14 +289,34 @@ impl Soldier {
                 velocity,
                 timeout: weapon.timeout as i16,
                 hit_multiply: weapon.hit_multiply,
-                team: Team::None,
+                team: self.team,
+                owner: self.num,
                 sprite: weapon.bullet_sprite,
             }));
 
             self.control.drop = false;
+            self.control.throw = false;
+            self.weapons[self.active_weapon] =
+                Weapon::new(WeaponKind::NoWeapon, state.realistic_mode);
             self.body_apply_animation(Anim::Stand, 1);
         }
 
+        // drop weapon, or throw it along the aim direction
+        if self.body_animation.id == Anim::ThrowWeapon
+            && self.body_animation.frame == 19
+            && self.primary_weapon().kind != WeaponKind::Knife
+        {
+            let aim_x = self.control.mouse_aim_x as f32;
+            let aim_y = self.control.mouse_aim_y as f32;
+            let dir = vec2normalize(vec2(aim_x, aim_y) - self.skeleton.pos(15));
+            let speed = iif!(self.control.throw, 5.0, 1.5);
+            let velocity = dir * speed + self.particle.velocity;
+
+            self.drop_weapon(emitter, velocity, state.realistic_mode);
+            self.control.drop = false;
+            self.control.throw = false;
+        }
+
         // Punch!
         if !self.dead_meat {
             if (self.body_animation.id == Anim::Punch) && (self.body_animation.frame == 11) {
@@ -368,17 +430,35 @@ impl Soldier {
             }
 
             if self.idle_random == 0 {
+                // no cigar yet: take one out, an unlit one gets a match, a lit one is smoked
                 if self.idle_time == 0 {
-                    self.body_apply_animation(Anim::Smoke, 1);
+                    let anim = match self.has_cigar {
+                        0 => Anim::Cigar,
+                        10 => Anim::Match,
+                        _ => Anim::Smoke,
+                    };
+
+                    self.body_apply_animation(anim, 1);
                     self.idle_time = DEFAULT_IDLETIME;
                 }
 
+                if self.body_animation.frame == self.body_animation.num_frames() {
+                    if self.body_animation.id == Anim::Cigar {
+                        self.has_cigar = 10;
+                    } else if self.body_animation.id == Anim::Match {
+                        self.has_cigar = 5;
+                    }
+                }
+
                 if (self.body_animation.id == Anim::Smoke) && (self.body_animation.frame == 17) {
                     self.body_animation.frame += 1;
                 }
 
                 if !self.dead_meat {
-                    if (self.idle_time == 1) && (self.body_animation.id != Anim::Smoke)
+                    if (self.idle_time == 1)
+                        && !self
+                            .body_animation
+                            .is_any(&[Anim::Cigar, Anim::Match, Anim::Smoke])
                         && (self.legs_animation.id == Anim::Stand)
                     {
                         self.idle_time = DEFAULT_IDLETIME;
@@ -884,8 +964,94 @@ impl Soldier {
     }
 }
 
+impl Action {
+    pub fn values() -> &'static [Action] {
+        static VALUES: &[Action] = &[
+            Action::Left,
+            Action::Right,
+            Action::Jump,
+            Action::Crouch,
+            Action::Prone,
+            Action::Fire,
+            Action::Jet,
+            Action::Grenade,
+            Action::ChangeWeapon,
+            Action::ThrowWeapon,
+            Action::DropWeapon,
+            Action::Reload,
+            Action::FlagThrow,
+            Action::Use,
+            Action::Chat,
+            Action::TeamChat,
+            Action::Radio,
+        ];
+
+        VALUES
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Action::Left => "Left",
+            Action::Right => "Right",
+            Action::Jump => "Jump",
+            Action::Crouch => "Crouch",
+            Action::Prone => "Prone",
+            Action::Fire => "Fire",
+            Action::Jet => "Jet",
+            Action::Grenade => "Grenade",
+            Action::ChangeWeapon => "ChangeWeapon",
+            Action::ThrowWeapon => "ThrowWeapon",
+            Action::DropWeapon => "DropWeapon",
+            Action::Reload => "Reload",
+            Action::FlagThrow => "FlagThrow",
+            Action::Use => "Use",
+            Action::Chat => "Chat",
+            Action::TeamChat => "TeamChat",
+            Action::Radio => "Radio",
+        }
+    }
+
+    pub fn chat_kind(&self) -> Option<ChatKind> {
+        match *self {
+            Action::Chat => Some(ChatKind::All),
+            Action::TeamChat => Some(ChatKind::Team),
+            Action::Radio => Some(ChatKind::Radio),
+            _ => None,
+        }
+    }
+}
+
 impl Control {
+    pub fn set_action(&mut self, action: Action, active: bool) {
+        match action {
+            Action::Left => self.left = active,
+            Action::Right => self.right = active,
+            Action::Jump => self.up = active,
+            Action::Crouch => self.down = active,
+            Action::Prone => self.prone = active,
+            Action::Fire => self.fire = active,
+            Action::Jet => self.jets = active,
+            Action::Grenade => self.grenade = active,
+            Action::ChangeWeapon => self.change = active,
+            Action::ThrowWeapon => self.throw = active,
+            Action::DropWeapon => self.drop = active,
+            Action::Reload => self.reload = active,
+            Action::FlagThrow => self.flag_throw = active,
+            Action::Use => self.use_item = active,
+            // the soldier doesn't do anything, see Bindings::chat_key
+            Action::Chat | Action::TeamChat | Action::Radio => (),
+        }
+    }
+
     pub fn free_controls(&mut self) {
         *self = Default::default();
     }
+
+    // The local player aims wherever the cursor points in the world.
+    pub fn aim_at_cursor(&mut self, state: &MainState) {
+        self.mouse_aim_x =
+            (state.mouse.x - state.game_width as f32 / 2.0 + state.camera.x).round() as i32;
+        self.mouse_aim_y =
+            (state.mouse.y - state.game_height as f32 / 2.0 + state.camera.y).round() as i32;
+    }
 }
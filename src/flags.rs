@@ -0,0 +1,228 @@
+use super::*;
+
+const SECOND: i32 = 60;
+const TOUCH_RADIUS: f32 = 15.0;
+const RETURN_TIME: i32 = SECOND * 20;
+const PICKUP_DELAY: i32 = SECOND;
+const THROW_SPEED: f32 = 6.0;
+
+// flag.po: particles 1 and 2 are the bottom and the top of the pole, 3 and 4 the free
+// corners of the cloth that hangs from the upper half of the pole.
+const POLE: [usize; 2] = [1, 2];
+const CLOTH: [usize; 2] = [3, 4];
+
+lazy_static! {
+    static ref FLAG_SKELETON: ParticleSystem =
+        ParticleSystem::load_from_file("flag.po", 4.0, 1.0, GRAV, 0.0, 0.99);
+}
+
+#[derive(Debug, Clone)]
+pub struct Flag {
+    pub team: Team,
+    pub base: Vec2,
+    pub skeleton: ParticleSystem,
+    pub holder: Option<usize>,
+    pub thrower: Option<usize>,
+    pub pickup_delay: i32,
+    pub away_time: i32,
+    home: bool,
+}
+
+impl Flag {
+    pub fn initialize() {
+        lazy_static::initialize(&FLAG_SKELETON);
+    }
+
+    pub fn new(team: Team, base: Vec2) -> Flag {
+        let mut flag = Flag {
+            team,
+            base,
+            skeleton: FLAG_SKELETON.clone(),
+            holder: None,
+            thrower: None,
+            pickup_delay: 0,
+            away_time: 0,
+            home: true,
+        };
+
+        flag.return_to_base();
+        flag
+    }
+
+    // Capture the flag puts both team flags on the map, infiltration only the bravo flag
+    // that alpha has to steal, and the yellow flag is fought over by everyone.
+    pub fn from_map(map: &MapFile, mode: GameMode) -> Vec<Flag> {
+        let kinds: &[(SpawnpointKind, Team)] = match mode {
+            GameMode::CaptureTheFlag => &[
+                (SpawnpointKind::AlphaFlag, Team::Alpha),
+                (SpawnpointKind::BravoFlag, Team::Bravo),
+            ],
+            GameMode::Infiltration => &[(SpawnpointKind::BravoFlag, Team::Bravo)],
+            GameMode::HoldTheFlag | GameMode::Pointmatch => {
+                &[(SpawnpointKind::YellowFlag, Team::None)]
+            }
+            _ => &[],
+        };
+
+        kinds
+            .iter()
+            .filter_map(|&(kind, team)| {
+                map.spawnpoints
+                    .iter()
+                    .find(|spawn| spawn.active && spawn.kind() == kind)
+                    .map(|spawn| Flag::new(team, vec2(spawn.x as f32, spawn.y as f32)))
+            })
+            .collect()
+    }
+
+    pub fn pos(&self) -> Vec2 {
+        lerp(self.skeleton.pos(POLE[0]), self.skeleton.pos(POLE[1]), 0.5)
+    }
+
+    pub fn at_base(&self) -> bool {
+        self.holder.is_none() && self.home
+    }
+
+    pub fn return_to_base(&mut self) {
+        let origin = FLAG_SKELETON.pos(POLE[0]);
+
+        for i in 1..=self.skeleton.particles().len() {
+            let pos = self.base + FLAG_SKELETON.pos(i) - origin;
+            *self.skeleton.pos_mut(i) = pos;
+            *self.skeleton.old_pos_mut(i) = pos;
+        }
+
+        self.holder = None;
+        self.thrower = None;
+        self.away_time = 0;
+        self.home = true;
+    }
+
+    // Gravity follows sv_gravity like it does for soldiers.
+    pub fn update(&mut self, map: &MapFile, gravity: f32, soldiers: &[Soldier]) {
+        self.skeleton.set_gravity(gravity);
+
+        if self.pickup_delay > 0 {
+            self.pickup_delay -= 1;
+        }
+
+        let holder = self
+            .holder
+            .and_then(|num| soldiers.iter().find(|s| s.num == num));
+
+        if let Some(soldier) = holder {
+            if soldier.dead_meat {
+                self.release(soldier.particle.velocity, soldier.num);
+            } else if soldier.control.flag_throw {
+                let aim = vec2(
+                    soldier.control.mouse_aim_x as f32,
+                    soldier.control.mouse_aim_y as f32,
+                );
+
+                let dir = vec2normalize(aim - soldier.skeleton.pos(15));
+                self.release(soldier.particle.velocity + dir * THROW_SPEED, soldier.num);
+            } else {
+                self.pin_pole(soldier.skeleton.pos(16));
+                return;
+            }
+        }
+
+        // carried by someone who left the game
+        self.holder = None;
+
+        if self.home {
+            self.pin_pole(self.base);
+            return;
+        }
+
+        // a loose flag falls onto the map and goes home if nobody picks it up in time
+
+        self.skeleton.do_verlet_timestep();
+
+        for i in 1..=self.skeleton.particles().len() {
+            self.check_map_collision(map, i);
+        }
+
+        self.away_time += 1;
+
+        if self.away_time > RETURN_TIME || map.out_of_bounds(self.pos()) {
+            self.return_to_base();
+        }
+    }
+
+    // Touching your own team's flag away from its base returns it, any other flag
+    // that isn't carried gets picked up.
+    pub fn check_soldier_collision(&mut self, soldier: &Soldier) -> bool {
+        if self.holder.is_some() || soldier.dead_meat || !soldier.active {
+            return false;
+        }
+
+        if self.pickup_delay > 0 && self.thrower == Some(soldier.num) {
+            return false;
+        }
+
+        if distance(self.pos(), soldier.body_center()) > TOUCH_RADIUS {
+            return false;
+        }
+
+        if self.team != Team::None && self.team == soldier.team {
+            if !self.at_base() {
+                self.return_to_base();
+                return true;
+            }
+
+            return false;
+        }
+
+        self.holder = Some(soldier.num);
+        self.home = false;
+        self.away_time = 0;
+        true
+    }
+
+    fn release(&mut self, velocity: Vec2, num: usize) {
+        for i in 1..=self.skeleton.particles().len() {
+            *self.skeleton.old_pos_mut(i) = self.skeleton.pos(i) - velocity;
+        }
+
+        self.holder = None;
+        self.thrower = Some(num);
+        self.pickup_delay = PICKUP_DELAY;
+    }
+
+    // The pole stands upright on its anchor, only the cloth moves freely and trails
+    // behind whoever carries it.
+    fn pin_pole(&mut self, anchor: Vec2) {
+        let origin = FLAG_SKELETON.pos(POLE[0]);
+
+        for i in POLE.iter() {
+            let pos = anchor + FLAG_SKELETON.pos(*i) - origin;
+            *self.skeleton.old_pos_mut(*i) = self.skeleton.pos(*i);
+            *self.skeleton.pos_mut(*i) = pos;
+        }
+
+        self.skeleton.do_verlet_timestep_of(&CLOTH);
+
+        for i in POLE.iter() {
+            *self.skeleton.pos_mut(*i) = anchor + FLAG_SKELETON.pos(*i) - origin;
+        }
+    }
+
+    fn check_map_collision(&mut self, map: &MapFile, i: usize) {
+        let pos = self.skeleton.pos(i);
+
+        for p in map.sector_polys(pos) {
+            let p = (*p - 1) as usize;
+
+            if map.polygons[p].flag_collides() && map.point_in_poly_edges(pos.x, pos.y, p as i32) {
+                let mut dist = 0.0;
+                let mut k = 0;
+                let perp =
+                    vec2normalize(map.closest_perpendicular(p as i32, pos, &mut dist, &mut k));
+
+                *self.skeleton.pos_mut(i) = self.skeleton.old_pos(i) - perp * dist;
+                break;
+            }
+        }
+    }
+}
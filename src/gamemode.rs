@@ -0,0 +1,603 @@
+use super::*;
+
+const SECOND: i32 = 60;
+const ROUND_END_TIME: i32 = SECOND * 5;
+const CAPTURE_RADIUS: f32 = 20.0;
+const CAPTURE_POINTS: i32 = 20;
+const INFILTRATION_CAPTURE_POINTS: i32 = 30;
+const INFILTRATION_DEFEND_POINTS: i32 = 5;
+const HOLD_POINTS_INTERVAL: i32 = SECOND * 5;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GameMode {
+    Deathmatch,
+    Pointmatch,
+    TeamMatch,
+    CaptureTheFlag,
+    Rambomatch,
+    Infiltration,
+    HoldTheFlag,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Winner {
+    Player(usize),
+    Team(Team),
+    Draw,
+}
+
+// Scores, limits and objectives of the round being played. Nothing in here touches
+// rendering or input, so rounds can be simulated without a window.
+#[derive(Debug, Clone)]
+pub struct GameRound {
+    pub mode: GameMode,
+    pub team_scores: [i32; 5],
+    pub score_limit: i32,
+    pub time_left: Option<i32>,
+    pub winner: Option<Winner>,
+    pub end_timer: i32,
+    pub capture_points: Vec<(Team, Vec2)>,
+    hold_timer: i32,
+}
+
+impl GameMode {
+    pub fn values() -> &'static [GameMode] {
+        static VALUES: &[GameMode] = &[
+            GameMode::Deathmatch,
+            GameMode::Pointmatch,
+            GameMode::TeamMatch,
+            GameMode::CaptureTheFlag,
+            GameMode::Rambomatch,
+            GameMode::Infiltration,
+            GameMode::HoldTheFlag,
+        ];
+
+        VALUES
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn short_name(&self) -> &'static str {
+        match *self {
+            GameMode::Deathmatch => "DM",
+            GameMode::Pointmatch => "PM",
+            GameMode::TeamMatch => "TDM",
+            GameMode::CaptureTheFlag => "CTF",
+            GameMode::Rambomatch => "RM",
+            GameMode::Infiltration => "INF",
+            GameMode::HoldTheFlag => "HTF",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GameMode> {
+        match name.trim().to_lowercase().as_str() {
+            "dm" | "deathmatch" => Some(GameMode::Deathmatch),
+            "pm" | "pointmatch" => Some(GameMode::Pointmatch),
+            "tdm" | "teammatch" => Some(GameMode::TeamMatch),
+            "ctf" | "capturetheflag" => Some(GameMode::CaptureTheFlag),
+            "rm" | "rambomatch" => Some(GameMode::Rambomatch),
+            "inf" | "infiltration" => Some(GameMode::Infiltration),
+            "htf" | "holdtheflag" => Some(GameMode::HoldTheFlag),
+            _ => None,
+        }
+    }
+
+    pub fn teams(&self) -> usize {
+        match *self {
+            GameMode::TeamMatch => 4,
+            GameMode::CaptureTheFlag | GameMode::Infiltration | GameMode::HoldTheFlag => 2,
+            _ => 0,
+        }
+    }
+
+    pub fn default_score_limit(&self) -> i32 {
+        match *self {
+            GameMode::Deathmatch | GameMode::Pointmatch | GameMode::Rambomatch => 30,
+            GameMode::TeamMatch => 60,
+            GameMode::CaptureTheFlag => 10,
+            GameMode::Infiltration => 90,
+            GameMode::HoldTheFlag => 80,
+        }
+    }
+}
+
+impl GameRound {
+    pub fn new(map: &MapFile, config: &ServerConfig) -> GameRound {
+        let mode = config.game_mode;
+
+        let capture_points = [
+            (SpawnpointKind::AlphaFlag, Team::Alpha),
+            (SpawnpointKind::BravoFlag, Team::Bravo),
+        ]
+        .iter()
+        .filter_map(|&(kind, team)| {
+            map.spawnpoints
+                .iter()
+                .find(|spawn| spawn.active && spawn.kind() == kind)
+                .map(|spawn| (team, vec2(spawn.x as f32, spawn.y as f32)))
+        })
+        .collect();
+
+        GameRound {
+            mode,
+            team_scores: [0; 5],
+            score_limit: config
+                .score_limit
+                .unwrap_or_else(|| mode.default_score_limit()),
+            time_left: config.time_limit.map(|minutes| minutes * 60 * SECOND),
+            winner: None,
+            end_timer: ROUND_END_TIME,
+            capture_points,
+            hold_timer: HOLD_POINTS_INTERVAL,
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.winner.is_some() && self.end_timer <= 0
+    }
+
+    pub fn update(
+        &mut self,
+        map: &MapFile,
+        flags: &mut [Flag],
+        things: &[Thing],
+        soldiers: &mut [Soldier],
+        realistic: bool,
+        emitter: &mut Vec<EmitterItem>,
+    ) {
+        if self.winner.is_some() {
+            self.end_timer -= 1;
+            return;
+        }
+
+        match self.mode {
+            GameMode::CaptureTheFlag | GameMode::Infiltration => {
+                self.check_captures(flags, soldiers)
+            }
+            GameMode::HoldTheFlag => self.update_hold(flags, soldiers),
+            GameMode::Rambomatch => Self::spawn_bow(map, things, soldiers, realistic, emitter),
+            _ => {}
+        }
+
+        if let Some(time_left) = self.time_left.as_mut() {
+            *time_left -= 1;
+        }
+
+        self.check_limits(soldiers);
+    }
+
+    pub fn on_kill(&mut self, kill: &KillParams, soldiers: &mut [Soldier], flags: &[Flag]) {
+        if self.winner.is_some() {
+            return;
+        }
+
+        let victim_rambo = soldiers
+            .iter()
+            .any(|s| s.num == kill.victim && s.holds_bow());
+
+        if let Some(victim) = soldiers.iter_mut().find(|s| s.num == kill.victim) {
+            victim.deaths += 1;
+        }
+
+        let killer = match soldiers
+            .iter_mut()
+            .find(|s| s.num == kill.killer && kill.killer != kill.victim)
+        {
+            Some(killer) => killer,
+            None => return,
+        };
+
+        killer.kills += 1;
+
+        let points = match self.mode {
+            // the yellow flag doubles the points of whoever carries it
+            GameMode::Pointmatch => {
+                iif!(flags.iter().any(|f| f.holder == Some(killer.num)), 2, 1)
+            }
+            // only kills by or of the rambo count
+            GameMode::Rambomatch => iif!(killer.holds_bow() || victim_rambo, 1, 0),
+            _ => 1,
+        };
+
+        killer.score += points;
+
+        if self.mode == GameMode::TeamMatch && killer.team != Team::None {
+            self.team_scores[killer.team.index()] += 1;
+        }
+    }
+
+    // A flag is captured by bringing it to the capturing team's flag spawn, in capture
+    // the flag only while the team's own flag is home.
+    fn check_captures(&mut self, flags: &mut [Flag], soldiers: &mut [Soldier]) {
+        for i in 0..flags.len() {
+            let holder = match flags[i].holder {
+                Some(num) => num,
+                None => continue,
+            };
+
+            let soldier = match soldiers.iter_mut().find(|s| s.num == holder) {
+                Some(soldier) => soldier,
+                None => continue,
+            };
+
+            let own_flag_home = flags
+                .iter()
+                .filter(|f| f.team == soldier.team)
+                .all(|f| f.at_base());
+
+            let at_capture_point = self.capture_points.iter().any(|&(team, pos)| {
+                team == soldier.team && distance(pos, soldier.body_center()) < CAPTURE_RADIUS
+            });
+
+            if own_flag_home && at_capture_point {
+                let team = soldier.team;
+
+                if self.mode == GameMode::Infiltration {
+                    self.team_scores[team.index()] += INFILTRATION_CAPTURE_POINTS;
+                } else {
+                    self.team_scores[team.index()] += 1;
+                }
+
+                soldier.score += CAPTURE_POINTS;
+                soldier.caps += 1;
+                flags[i].return_to_base();
+            }
+        }
+
+        // bravo scores for every stretch of time its flag stays untouched
+        if self.mode == GameMode::Infiltration {
+            self.hold_timer -= 1;
+
+            if self.hold_timer <= 0 {
+                self.hold_timer = HOLD_POINTS_INTERVAL;
+
+                if flags.iter().all(|f| f.at_base()) {
+                    self.team_scores[Team::Bravo.index()] += INFILTRATION_DEFEND_POINTS;
+                }
+            }
+        }
+    }
+
+    // The team carrying the yellow flag scores a point every few seconds.
+    fn update_hold(&mut self, flags: &[Flag], soldiers: &[Soldier]) {
+        self.hold_timer -= 1;
+
+        if self.hold_timer > 0 {
+            return;
+        }
+
+        self.hold_timer = HOLD_POINTS_INTERVAL;
+
+        for flag in flags {
+            let team = flag
+                .holder
+                .and_then(|num| soldiers.iter().find(|s| s.num == num))
+                .map(|s| s.team);
+
+            if let Some(team) = team {
+                self.team_scores[team.index()] += 1;
+            }
+        }
+    }
+
+    // Rambomatch keeps exactly one bow in play, a new one appears at the bow spawn
+    // whenever nobody carries it and there is none lying around.
+    fn spawn_bow(
+        map: &MapFile,
+        things: &[Thing],
+        soldiers: &[Soldier],
+        realistic: bool,
+        emitter: &mut Vec<EmitterItem>,
+    ) {
+        let is_bow = |kind: &ThingKind| match *kind {
+            ThingKind::Weapon(weapon) => weapon.is_any(&[WeaponKind::Bow, WeaponKind::FlameBow]),
+            _ => false,
+        };
+
+        // a rambo killed this tick is still dropping theirs
+        let bow_on_map = things
+            .iter()
+            .any(|thing| thing.active && is_bow(&thing.kind))
+            || emitter.iter().any(|item| match *item {
+                EmitterItem::Thing(ref params) => is_bow(&params.kind),
+                _ => false,
+            });
+
+        if bow_on_map || soldiers.iter().any(|s| !s.dead_meat && s.holds_bow()) {
+            return;
+        }
+
+        let spawn = map
+            .spawnpoints
+            .iter()
+            .find(|spawn| spawn.active && spawn.kind() == SpawnpointKind::RamboBow)
+            .unwrap_or_else(|| map.team_spawnpoint(Team::None));
+
+        emitter.push(EmitterItem::Thing(ThingParams {
+            kind: ThingKind::Weapon(Weapon::new(WeaponKind::Bow, realistic)),
+            position: vec2(spawn.x as f32, spawn.y as f32),
+            velocity: vec2(0.0, 0.0),
+            rotation: 0.0,
+            owner: 0,
+            stuck: false,
+        }));
+    }
+
+    fn check_limits(&mut self, soldiers: &[Soldier]) {
+        let teams = self.mode.teams();
+
+        let leader = if teams > 0 {
+            let best = Team::values()[..teams]
+                .iter()
+                .max_by_key(|team| self.team_scores[team.index()])
+                .cloned();
+
+            best.map(|team| (Winner::Team(team), self.team_scores[team.index()]))
+        } else {
+            soldiers
+                .iter()
+                .max_by_key(|s| s.score)
+                .map(|s| (Winner::Player(s.num), s.score))
+        };
+
+        let (leader, score) = match leader {
+            Some(leader) => leader,
+            None => return,
+        };
+
+        if score >= self.score_limit {
+            self.winner = Some(leader);
+        } else if self.time_left.is_some_and(|time| time <= 0) {
+            let tied = if teams > 0 {
+                Team::values()[..teams]
+                    .iter()
+                    .filter(|team| self.team_scores[team.index()] == score)
+                    .count()
+            } else {
+                soldiers.iter().filter(|s| s.score == score).count()
+            };
+
+            self.winner = Some(iif!(tied > 1, Winner::Draw, leader));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::*;
+
+    fn round(mode: GameMode) -> GameRound {
+        GameRound {
+            mode,
+            team_scores: [0; 5],
+            score_limit: 10,
+            time_left: None,
+            winner: None,
+            end_timer: ROUND_END_TIME,
+            capture_points: Vec::new(),
+            hold_timer: HOLD_POINTS_INTERVAL,
+        }
+    }
+
+    fn soldiers(teams: &[Team]) -> Vec<Soldier> {
+        let spawn = MapSpawnpoint {
+            active: true,
+            x: 0,
+            y: 0,
+            team: 0,
+        };
+
+        teams
+            .iter()
+            .enumerate()
+            .map(|(i, team)| {
+                let mut soldier = Soldier::new(&spawn, false);
+                soldier.num = i + 1;
+                soldier.team = *team;
+                soldier
+            })
+            .collect()
+    }
+
+    fn kill(killer: usize, victim: usize) -> KillParams {
+        KillParams {
+            killer,
+            victim,
+            weapon: WeaponKind::Ak74,
+        }
+    }
+
+    #[test]
+    fn team_score_limit_ends_round() {
+        let mut round = round(GameMode::TeamMatch);
+        round.team_scores[Team::Bravo.index()] = 9;
+        round.check_limits(&[]);
+        assert_eq!(round.winner, None);
+
+        round.team_scores[Team::Bravo.index()] = 10;
+        round.check_limits(&[]);
+        assert_eq!(round.winner, Some(Winner::Team(Team::Bravo)));
+    }
+
+    #[test]
+    fn time_limit_ends_round_with_leader_or_draw() {
+        let mut round = round(GameMode::CaptureTheFlag);
+        round.team_scores[Team::Alpha.index()] = 2;
+        round.team_scores[Team::Bravo.index()] = 1;
+        round.time_left = Some(1);
+        round.check_limits(&[]);
+        assert_eq!(round.winner, None);
+
+        round.time_left = Some(0);
+        round.check_limits(&[]);
+        assert_eq!(round.winner, Some(Winner::Team(Team::Alpha)));
+
+        round.winner = None;
+        round.team_scores[Team::Bravo.index()] = 2;
+        round.check_limits(&[]);
+        assert_eq!(round.winner, Some(Winner::Draw));
+    }
+
+    #[test]
+    fn player_score_limit_ends_round() {
+        let mut round = round(GameMode::Deathmatch);
+        let mut soldiers = soldiers(&[Team::None, Team::None]);
+        soldiers[1].score = 10;
+        round.check_limits(&soldiers);
+        assert_eq!(round.winner, Some(Winner::Player(2)));
+    }
+
+    #[test]
+    fn kills_score_in_deathmatch() {
+        let mut round = round(GameMode::Deathmatch);
+        let mut soldiers = soldiers(&[Team::None, Team::None]);
+
+        round.on_kill(&kill(1, 2), &mut soldiers, &[]);
+        assert_eq!((soldiers[0].kills, soldiers[0].score), (1, 1));
+        assert_eq!((soldiers[1].deaths, soldiers[1].score), (1, 0));
+
+        // suicides only count as deaths
+        round.on_kill(&kill(2, 2), &mut soldiers, &[]);
+        assert_eq!((soldiers[1].kills, soldiers[1].deaths), (0, 2));
+    }
+
+    #[test]
+    fn flag_doubles_points_in_pointmatch() {
+        let mut round = round(GameMode::Pointmatch);
+        let mut soldiers = soldiers(&[Team::None, Team::None]);
+        let mut flag = Flag::new(Team::None, Vec2::zero());
+
+        round.on_kill(&kill(1, 2), &mut soldiers, &[flag.clone()]);
+        assert_eq!(soldiers[0].score, 1);
+
+        flag.holder = Some(1);
+        round.on_kill(&kill(1, 2), &mut soldiers, &[flag]);
+        assert_eq!(soldiers[0].score, 3);
+    }
+
+    #[test]
+    fn kills_score_for_the_team_in_teammatch() {
+        let mut round = round(GameMode::TeamMatch);
+        let mut soldiers = soldiers(&[Team::Alpha, Team::Bravo]);
+
+        round.on_kill(&kill(2, 1), &mut soldiers, &[]);
+        assert_eq!(round.team_scores[Team::Bravo.index()], 1);
+        assert_eq!(round.team_scores[Team::Alpha.index()], 0);
+    }
+
+    #[test]
+    fn only_rambo_kills_score_in_rambomatch() {
+        let mut round = round(GameMode::Rambomatch);
+        let mut soldiers = soldiers(&[Team::None, Team::None, Team::None]);
+
+        round.on_kill(&kill(1, 2), &mut soldiers, &[]);
+        assert_eq!(soldiers[0].score, 0);
+
+        let active = soldiers[0].active_weapon;
+        soldiers[0].weapons[active] = Weapon::new(WeaponKind::Bow, false);
+        round.on_kill(&kill(1, 2), &mut soldiers, &[]);
+        assert_eq!(soldiers[0].score, 1);
+
+        // killing the rambo counts as well
+        round.on_kill(&kill(3, 1), &mut soldiers, &[]);
+        assert_eq!(soldiers[2].score, 1);
+    }
+
+    #[test]
+    fn rambomatch_keeps_one_bow_in_play() {
+        let mut state = test_state(GameMode::Rambomatch);
+        let mut soldiers = Vec::new();
+        let mut bots: Vec<Bot> = Vec::new();
+        let mut emitter = Vec::new();
+
+        // spawned away from the bow so it isn't picked up on its own
+        add_soldier(&state, &mut soldiers, Team::Alpha);
+
+        let bows = |state: &MainState| {
+            state
+                .things
+                .iter()
+                .filter(|thing| match thing.kind {
+                    ThingKind::Weapon(weapon) => thing.active && weapon.kind == WeaponKind::Bow,
+                    _ => false,
+                })
+                .map(|thing| thing.particle.pos)
+                .collect::<Vec<_>>()
+        };
+
+        for _ in 0..SECOND {
+            state.update(&mut soldiers, &mut bots, &mut emitter);
+        }
+
+        let spawn = state
+            .map
+            .spawnpoints
+            .iter()
+            .find(|spawn| spawn.kind() == SpawnpointKind::RamboBow)
+            .unwrap();
+        let spawn = vec2(spawn.x as f32, spawn.y as f32);
+
+        assert_eq!(bows(&state).len(), 1);
+        assert!(distance(bows(&state)[0], spawn) < 30.0);
+
+        // nothing new while the rambo is alive
+        state.things.clear();
+        let active = soldiers[0].active_weapon;
+        soldiers[0].weapons[active] = Weapon::new(WeaponKind::Bow, false);
+        state.update(&mut soldiers, &mut bots, &mut emitter);
+        assert!(bows(&state).is_empty());
+
+        // the rambo's death drops the bow, which is the one in play then
+        soldiers[0].health = 0.0;
+        state.update(&mut soldiers, &mut bots, &mut emitter);
+        assert!(soldiers[0].dead_meat);
+        assert_eq!(bows(&state).len(), 1);
+    }
+
+    #[test]
+    fn flag_capture_scores_for_the_team() {
+        let mut state = test_state(GameMode::CaptureTheFlag);
+        let mut soldiers = Vec::new();
+        let mut emitter = Vec::new();
+        let num = add_soldier(&state, &mut soldiers, Team::Alpha);
+
+        // body_center is 10 units above the particle
+        let at = |soldier: &mut Soldier, pos: Vec2| soldier.particle.pos = pos + vec2(0.0, 10.0);
+
+        let enemy = state
+            .flags
+            .iter()
+            .position(|f| f.team == Team::Bravo)
+            .unwrap();
+        let enemy_base = state.flags[enemy].pos();
+        at(&mut soldiers[0], enemy_base);
+        assert!(state.flags[enemy].check_soldier_collision(&soldiers[0]));
+        assert_eq!(state.flags[enemy].holder, Some(num));
+
+        let (_, home) = *state
+            .round
+            .capture_points
+            .iter()
+            .find(|&&(team, _)| team == Team::Alpha)
+            .unwrap();
+
+        at(&mut soldiers[0], home);
+
+        state.round.update(
+            &state.map,
+            &mut state.flags,
+            &state.things,
+            &mut soldiers,
+            false,
+            &mut emitter,
+        );
+
+        assert_eq!(state.round.team_scores[Team::Alpha.index()], 1);
+        assert_eq!(soldiers[0].caps, 1);
+        assert_eq!(soldiers[0].score, CAPTURE_POINTS);
+        assert!(state.flags[enemy].at_base());
+    }
+}
@@ -0,0 +1,177 @@
+use super::*;
+
+const SECOND: i32 = 60;
+const KIT_RESPAWN_TIME: i32 = SECOND * 15;
+const BONUS_INTERVAL: i32 = SECOND * 90;
+const BONUS_TIMEOUT: i32 = SECOND * 20;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum KitKind {
+    Medikit,
+    GrenadeKit,
+    ClusterKit,
+    Vest,
+    Berserker,
+    Predator,
+    Flamegod,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Bonus {
+    Berserker,
+    Predator,
+    Flamegod,
+}
+
+pub struct KitSpawner {
+    kit_timer: i32,
+    bonus_timer: i32,
+}
+
+impl KitKind {
+    pub fn values() -> &'static [KitKind] {
+        static VALUES: &[KitKind] = &[
+            KitKind::Medikit,
+            KitKind::GrenadeKit,
+            KitKind::ClusterKit,
+            KitKind::Vest,
+            KitKind::Berserker,
+            KitKind::Predator,
+            KitKind::Flamegod,
+        ];
+
+        VALUES
+    }
+
+    pub fn spawnpoint_kind(&self) -> SpawnpointKind {
+        match *self {
+            KitKind::Medikit => SpawnpointKind::Medkits,
+            KitKind::GrenadeKit => SpawnpointKind::Grenades,
+            KitKind::ClusterKit => SpawnpointKind::Clusters,
+            KitKind::Vest => SpawnpointKind::Vest,
+            KitKind::Berserker => SpawnpointKind::Berserker,
+            KitKind::Predator => SpawnpointKind::Predator,
+            KitKind::Flamegod => SpawnpointKind::Flamer,
+        }
+    }
+
+    pub fn sprite(&self) -> gfx::Object {
+        match *self {
+            KitKind::Medikit => gfx::Object::Medikit,
+            KitKind::GrenadeKit => gfx::Object::Grenadekit,
+            KitKind::ClusterKit => gfx::Object::Clusterkit,
+            KitKind::Vest => gfx::Object::Vestkit,
+            KitKind::Berserker => gfx::Object::Berserkerkit,
+            KitKind::Predator => gfx::Object::Predatorkit,
+            KitKind::Flamegod => gfx::Object::Flamerkit,
+        }
+    }
+
+    // Medikits and grenade kits are always on the map, the rest show up now and then
+    // depending on the bonus frequency and disappear if nobody takes them.
+    pub fn is_bonus(&self) -> bool {
+        !matches!(*self, KitKind::Medikit | KitKind::GrenadeKit)
+    }
+
+    pub fn timeout(&self) -> i32 {
+        iif!(self.is_bonus(), BONUS_TIMEOUT, i32::MAX)
+    }
+}
+
+impl Bonus {
+    pub fn duration(&self) -> i32 {
+        match *self {
+            Bonus::Berserker => SECOND * 15,
+            Bonus::Predator => SECOND * 25,
+            Bonus::Flamegod => SECOND * 10,
+        }
+    }
+}
+
+impl KitSpawner {
+    pub fn new() -> KitSpawner {
+        KitSpawner {
+            kit_timer: 0,
+            bonus_timer: BONUS_INTERVAL,
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        map: &MapFile,
+        things: &[Thing],
+        config: &ServerConfig,
+        emitter: &mut Vec<EmitterItem>,
+    ) {
+        self.kit_timer -= 1;
+
+        if self.kit_timer <= 0 {
+            self.kit_timer = KIT_RESPAWN_TIME;
+
+            for (kind, max) in &[
+                (KitKind::Medikit, map.medikits),
+                (KitKind::GrenadeKit, map.grenade_packs),
+            ] {
+                for _ in Self::count(*kind, things)..usize::from(*max) {
+                    Self::spawn(*kind, map, emitter);
+                }
+            }
+        }
+
+        if config.bonus_frequency > 0 {
+            self.bonus_timer -= 1;
+
+            if self.bonus_timer <= 0 {
+                self.bonus_timer = BONUS_INTERVAL / i32::from(config.bonus_frequency);
+
+                let kinds: Vec<KitKind> = KitKind::values()
+                    .iter()
+                    .filter(|kind| kind.is_bonus() && Self::count(**kind, things) == 0)
+                    .filter(|kind| !Self::spawnpoints(**kind, map).is_empty())
+                    .cloned()
+                    .collect();
+
+                if !kinds.is_empty() {
+                    let kind = kinds[rand::random::<usize>() % kinds.len()];
+                    Self::spawn(kind, map, emitter);
+                }
+            }
+        }
+    }
+
+    fn count(kind: KitKind, things: &[Thing]) -> usize {
+        things
+            .iter()
+            .filter(|thing| match thing.kind {
+                ThingKind::Kit(k) => thing.active && k == kind,
+                _ => false,
+            })
+            .count()
+    }
+
+    fn spawnpoints(kind: KitKind, map: &MapFile) -> Vec<&MapSpawnpoint> {
+        map.spawnpoints
+            .iter()
+            .filter(|spawn| spawn.active && spawn.kind() == kind.spawnpoint_kind())
+            .collect()
+    }
+
+    fn spawn(kind: KitKind, map: &MapFile, emitter: &mut Vec<EmitterItem>) {
+        let spawns = Self::spawnpoints(kind, map);
+
+        if spawns.is_empty() {
+            return;
+        }
+
+        let spawn = spawns[rand::random::<usize>() % spawns.len()];
+
+        emitter.push(EmitterItem::Thing(ThingParams {
+            kind: ThingKind::Kit(kind),
+            position: vec2(spawn.x as f32, spawn.y as f32),
+            velocity: vec2(0.0, 0.0),
+            rotation: 0.0,
+            owner: 0,
+            stuck: false,
+        }));
+    }
+}
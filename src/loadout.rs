@@ -0,0 +1,224 @@
+use super::*;
+use ini::Ini;
+
+const MENU_X: f32 = 20.0;
+const MENU_Y: f32 = 60.0;
+const MENU_ROW_HEIGHT: f32 = 22.0;
+const MENU_COLUMN_WIDTH: f32 = 150.0;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Loadout {
+    pub primary: WeaponKind,
+    pub secondary: WeaponKind,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum WeaponLimit {
+    Disabled,
+    Unlimited,
+    PerTeam(usize),
+}
+
+pub struct WeaponLimits {
+    limits: Vec<WeaponLimit>,
+}
+
+// Like in Soldat the menu asks for a primary first and then a secondary, and closes
+// once both are picked. Number keys pick from the column being asked for.
+pub struct WeaponMenu {
+    pub active: bool,
+    pub picking: WeaponGroup,
+    pub selection: Loadout,
+    pub available: Vec<bool>,
+}
+
+impl Default for Loadout {
+    fn default() -> Loadout {
+        Loadout {
+            primary: WeaponKind::DesertEagles,
+            secondary: WeaponKind::Chainsaw,
+        }
+    }
+}
+
+impl Loadout {
+    pub fn primaries() -> &'static [WeaponKind] {
+        static VALUES: &[WeaponKind] = &[
+            WeaponKind::DesertEagles,
+            WeaponKind::MP5,
+            WeaponKind::Ak74,
+            WeaponKind::SteyrAUG,
+            WeaponKind::Spas12,
+            WeaponKind::Ruger77,
+            WeaponKind::M79,
+            WeaponKind::Barrett,
+            WeaponKind::Minimi,
+            WeaponKind::Minigun,
+        ];
+
+        VALUES
+    }
+
+    pub fn secondaries() -> &'static [WeaponKind] {
+        static VALUES: &[WeaponKind] = &[
+            WeaponKind::USSOCOM,
+            WeaponKind::Knife,
+            WeaponKind::Chainsaw,
+            WeaponKind::LAW,
+        ];
+
+        VALUES
+    }
+
+    pub fn with(&self, kind: WeaponKind) -> Loadout {
+        match kind.group() {
+            WeaponGroup::Primary => Loadout {
+                primary: kind,
+                ..*self
+            },
+            WeaponGroup::Secondary => Loadout {
+                secondary: kind,
+                ..*self
+            },
+            WeaponGroup::Other => *self,
+        }
+    }
+}
+
+impl WeaponLimits {
+    pub fn new() -> WeaponLimits {
+        WeaponLimits {
+            limits: vec![WeaponLimit::Unlimited; WeaponKind::values().len()],
+        }
+    }
+
+    pub fn get(&self, kind: WeaponKind) -> WeaponLimit {
+        self.limits[kind.index()]
+    }
+
+    pub fn set(&mut self, kind: WeaponKind, limit: WeaponLimit) {
+        self.limits[kind.index()] = limit;
+    }
+
+    // Reads the [WEAPONS] section, keyed by the weapon ini names. A value of 0
+    // disables the weapon, a negative value removes the limit and any other
+    // number caps how many players of the same team may carry it.
+    pub fn load_data(&mut self, cfg: &Ini) {
+        if let Some(data) = cfg.section(Some("WEAPONS".to_owned())) {
+            for kind in Loadout::primaries().iter().chain(Loadout::secondaries()) {
+                let weapon = Weapon::new(*kind, false);
+
+                if let Some(value) = data.get(weapon.ini_name) {
+                    let limit = match value.trim().parse::<i32>() {
+                        Ok(0) => WeaponLimit::Disabled,
+                        Ok(n) if n > 0 => WeaponLimit::PerTeam(n as usize),
+                        _ => WeaponLimit::Unlimited,
+                    };
+
+                    self.set(*kind, limit);
+                }
+            }
+        }
+    }
+
+    pub fn accepts(&self, kind: WeaponKind, team: Team, num: usize, soldiers: &[Soldier]) -> bool {
+        match self.get(kind) {
+            WeaponLimit::Disabled => false,
+            WeaponLimit::Unlimited => true,
+            WeaponLimit::PerTeam(max) => {
+                let used = soldiers
+                    .iter()
+                    .filter(|s| s.num != num && s.team == team)
+                    .filter(|s| s.loadout.primary == kind || s.loadout.secondary == kind)
+                    .count();
+
+                used < max
+            }
+        }
+    }
+
+    pub fn validate(
+        &self,
+        loadout: &Loadout,
+        team: Team,
+        num: usize,
+        soldiers: &[Soldier],
+    ) -> bool {
+        loadout.primary.group() == WeaponGroup::Primary
+            && loadout.secondary.group() == WeaponGroup::Secondary
+            && self.accepts(loadout.primary, team, num, soldiers)
+            && self.accepts(loadout.secondary, team, num, soldiers)
+    }
+}
+
+impl WeaponMenu {
+    pub fn new(selection: Loadout) -> WeaponMenu {
+        WeaponMenu {
+            active: true,
+            picking: WeaponGroup::Primary,
+            selection,
+            available: vec![true; WeaponKind::values().len()],
+        }
+    }
+
+    pub fn open(&mut self) {
+        self.active = true;
+        self.picking = WeaponGroup::Primary;
+    }
+
+    // The weapon under a number key, counted from 0 in the column being picked.
+    pub fn key_item(&self, index: usize) -> Option<WeaponKind> {
+        let column = iif!(
+            self.picking == WeaponGroup::Secondary,
+            Loadout::secondaries(),
+            Loadout::primaries()
+        );
+
+        column.get(index).cloned()
+    }
+
+    // Moves on to the secondaries after a primary, a secondary finishes the choice.
+    pub fn picked(&mut self, kind: WeaponKind) {
+        match kind.group() {
+            WeaponGroup::Primary => self.picking = WeaponGroup::Secondary,
+            WeaponGroup::Secondary => self.active = false,
+            WeaponGroup::Other => (),
+        }
+    }
+
+    pub fn refresh(&mut self, limits: &WeaponLimits, soldier: &Soldier, soldiers: &[Soldier]) {
+        for kind in WeaponKind::values() {
+            self.available[kind.index()] =
+                limits.accepts(*kind, soldier.team, soldier.num, soldiers);
+        }
+    }
+
+    pub fn item_pos(kind: WeaponKind) -> Vec2 {
+        let (column, row) = match kind.group() {
+            WeaponGroup::Secondary => (1.0, kind.index() - WeaponKind::USSOCOM.index()),
+            _ => (0.0, kind.index()),
+        };
+
+        vec2(
+            MENU_X + column * MENU_COLUMN_WIDTH,
+            MENU_Y + row as f32 * MENU_ROW_HEIGHT,
+        )
+    }
+
+    pub fn item_size() -> Vec2 {
+        vec2(MENU_COLUMN_WIDTH - 10.0, MENU_ROW_HEIGHT - 2.0)
+    }
+
+    pub fn item_at(&self, pos: Vec2) -> Option<WeaponKind> {
+        let size = Self::item_size();
+
+        Loadout::primaries()
+            .iter()
+            .chain(Loadout::secondaries())
+            .find(|kind| {
+                let p = Self::item_pos(**kind);
+                pos.x >= p.x && pos.x < p.x + size.x && pos.y >= p.y && pos.y < p.y + size.y
+            })
+            .cloned()
+    }
+}
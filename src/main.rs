@@ -6,6 +6,7 @@ extern crate clap;
 extern crate gfx2d;
 extern crate glutin;
 extern crate ini;
+extern crate rand;
 extern crate time;
 extern crate typenum;
 
@@ -14,31 +15,65 @@ macro_rules! iif(
 );
 
 mod anims;
+mod assets;
+mod bindings;
+mod bots;
 mod bullet;
 mod calc;
+mod chat;
+mod config;
+mod console;
 mod control;
+mod flags;
+mod gamemode;
+mod kits;
+mod loadout;
 mod mapfile;
 mod particles;
+mod pathfinding;
+mod player;
 mod render;
 mod soldier;
 mod state;
+mod stationary;
+#[cfg(test)]
+mod testing;
+mod things;
+mod waypoints;
 mod weapons;
 
 use anims::*;
+use assets::*;
+use bindings::*;
+use bots::*;
 use bullet::*;
 use calc::*;
+use chat::*;
+use config::*;
+use console::*;
 use control::*;
+use flags::*;
+use gamemode::*;
+use kits::*;
+use loadout::*;
 use mapfile::*;
 use particles::*;
+use pathfinding::*;
+use player::*;
 use render::*;
 use soldier::*;
 use state::*;
+use stationary::*;
+use things::*;
+use waypoints::*;
 use weapons::*;
 
 use clap::{App, Arg};
 use glutin::*;
 
 const GRAV: f32 = 0.06;
+const SCOPE_ZOOM: f32 = 0.5;
+const SCOPE_SHIFT: f32 = 120.0;
 
 fn main() {
     let cmd = App::new("Soldank")
@@ -51,36 +86,130 @@ fn main() {
                 .long("map")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("config")
+                .help("server config file")
+                .short("c")
+                .long("config")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("gamemode")
+                .help("game mode (dm, pm, tdm, ctf, rm, inf, htf)")
+                .short("g")
+                .long("gamemode")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("player")
+                .help("player profile file")
+                .short("p")
+                .long("player")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("bindings")
+                .help("key bindings file")
+                .short("k")
+                .long("bindings")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("bots")
+                .help("number of bots")
+                .short("b")
+                .long("bots")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("waypoints")
+                .help("generate waypoints from the map geometry")
+                .short("w")
+                .long("waypoints"),
+        )
+        .arg(
+            Arg::with_name("exec")
+                .help("console command to run at startup")
+                .short("e")
+                .long("exec")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("realistic")
+                .help("enable realistic mode")
+                .short("r")
+                .long("realistic"),
+        )
         .get_matches();
 
     AnimData::initialize();
     Soldier::initialize();
+    Flag::initialize();
+
+    let map = load_map(
+        cmd.value_of("map").unwrap_or("ctf_Ash"),
+        cmd.is_present("waypoints"),
+    );
 
-    let mut map_name = cmd.value_of("map").unwrap_or("ctf_Ash").to_owned();
-    map_name.push_str(".pms");
+    let load_config = || {
+        let mut config =
+            ServerConfig::load_from_file(cmd.value_of("config").unwrap_or("soldank.ini"));
+        config.realistic_mode |= cmd.is_present("realistic");
 
-    let map = MapFile::load_map_file(map_name.as_str());
+        if let Some(mode) = cmd.value_of("gamemode").and_then(GameMode::from_name) {
+            config.game_mode = mode;
+        }
+
+        if let Some(Ok(bots)) = cmd.value_of("bots").map(|v| v.parse::<usize>()) {
+            config.bots = bots;
+        }
+
+        config
+    };
 
     const W: u32 = 1280;
     const H: u32 = 720;
 
-    let mut state = MainState {
-        map,
-        game_width: W as f32 * (480.0 / H as f32),
-        game_height: 480.0,
-        camera: Vec2::zero(),
-        camera_prev: Vec2::zero(),
-        mouse: Vec2::zero(),
-        mouse_prev: Vec2::zero(),
-        gravity: GRAV,
-        zoom: 0.0,
-        bullets: vec![],
-    };
+    let mut state = MainState::new(map, load_config());
+    state.game_width = W as f32 * (480.0 / H as f32);
 
-    let mut soldier = Soldier::new(&state.map.spawnpoints[0]);
+    let load_player = || Player::load_from_file(cmd.value_of("player").unwrap_or("player.ini"));
+
+    let team = Team::balanced(&[], state.config.game_mode.teams());
+    let mut soldier = Soldier::new(state.map.team_spawnpoint(team), state.realistic_mode);
+    soldier.team = team;
+    soldier.player = load_player();
     state.camera = soldier.particle.pos;
 
+    let mut menu = WeaponMenu::new(soldier.loadout);
+    let load_bindings =
+        || Bindings::load_from_file(cmd.value_of("bindings").unwrap_or("bindings.ini"));
+    let mut bindings = load_bindings();
+
+    // the local player is always the first soldier
+    let mut soldiers = vec![soldier];
+    let mut bots: Vec<Bot> = Vec::new();
+    add_bots(&state, &mut soldiers, &mut bots);
+
     let mut emitter: Vec<EmitterItem> = Vec::new();
+    let mut console = Console::new();
+    let mut chat_logged = 0;
+
+    {
+        let mut env = ConsoleEnv {
+            state: &mut state,
+            soldiers: &mut soldiers,
+            emitter: &mut emitter,
+        };
+
+        console.exec_file(&mut env, "autoexec.cfg");
+
+        for line in cmd.values_of("exec").into_iter().flatten() {
+            console.execute(&mut env, line);
+        }
+    }
 
     // setup window, renderer & main loop
 
@@ -99,6 +228,7 @@ fn main() {
 
     let mut graphics = GameGraphics::new(&mut context);
     graphics.load_sprites(&mut context);
+    graphics.load_font(&mut context, &state);
     graphics.load_map(&mut context, &state.map);
 
     let time_start = time::precise_time_s();
@@ -111,42 +241,121 @@ fn main() {
 
     let mut zoomin_pressed = false;
     let mut zoomout_pressed = false;
-
-    let weapons: Vec<Weapon> = WeaponKind::values()
-        .iter()
-        .map(|k| Weapon::new(*k, false))
-        .collect();
+    let mut show_scores = false;
+    let mut scope_zoom = 0.0;
 
     while running {
         context.evt.poll_events(|e| {
             if let Event::WindowEvent { event, .. } = e {
                 match event {
                     WindowEvent::CloseRequested => running = false,
-                    WindowEvent::KeyboardInput { input, .. } => match input.virtual_keycode {
-                        Some(VirtualKeyCode::Escape) => running = false,
-                        Some(VirtualKeyCode::Add) => {
-                            zoomin_pressed = match input.state {
-                                ElementState::Pressed => true,
-                                ElementState::Released => false,
-                            }
+                    WindowEvent::KeyboardInput { input, .. }
+                        if input.virtual_keycode == Some(VirtualKeyCode::Grave)
+                            && input.state == ElementState::Pressed =>
+                    {
+                        console.active = !console.active;
+                        bindings.release_all(&mut soldiers[0].control);
+                        show_scores = false;
+                    }
+                    WindowEvent::KeyboardInput { input, .. } if console.active => {
+                        let mut env = ConsoleEnv {
+                            state: &mut state,
+                            soldiers: &mut soldiers,
+                            emitter: &mut emitter,
+                        };
+
+                        console.key_input(&input, &mut env);
+                    }
+                    WindowEvent::ReceivedCharacter(c) if console.active => console.char_input(c),
+                    WindowEvent::MouseInput { .. } if console.active => (),
+                    WindowEvent::KeyboardInput { input, .. } if state.chat.typing() => {
+                        state.chat.key_input(&input, &soldiers);
+                    }
+                    WindowEvent::ReceivedCharacter(c) => state.chat.char_input(c),
+                    WindowEvent::MouseInput { .. } if state.chat.typing() => (),
+                    WindowEvent::KeyboardInput { input, .. } if state.chat.radio_menu => {
+                        state.chat.radio_input(&input, &soldiers);
+                    }
+                    WindowEvent::KeyboardInput { input, .. } => match bindings.chat_key(&input) {
+                        Some(kind) => {
+                            bindings.release_all(&mut soldiers[0].control);
+                            state.chat.open(kind);
                         }
-                        Some(VirtualKeyCode::Subtract) => {
-                            zoomout_pressed = match input.state {
-                                ElementState::Pressed => true,
-                                ElementState::Released => false,
+                        None => match input.virtual_keycode {
+                            Some(VirtualKeyCode::Escape) => running = false,
+                            Some(VirtualKeyCode::Add) => {
+                                zoomin_pressed = match input.state {
+                                    ElementState::Pressed => true,
+                                    ElementState::Released => false,
+                                }
                             }
-                        }
-                        Some(VirtualKeyCode::Tab) => {
-                            if input.state == ElementState::Pressed {
-                                let index = soldier.primary_weapon().kind.index();
-                                let index = (index + 1) % (WeaponKind::NoWeapon.index() + 1);
-                                soldier.weapons[soldier.active_weapon] = weapons[index];
+                            Some(VirtualKeyCode::Subtract) => {
+                                zoomout_pressed = match input.state {
+                                    ElementState::Pressed => true,
+                                    ElementState::Released => false,
+                                }
                             }
-                        }
-                        _ => soldier.update_keys(&input),
+                            Some(VirtualKeyCode::Tab) => {
+                                show_scores = input.state == ElementState::Pressed;
+                            }
+                            Some(VirtualKeyCode::F5) => {
+                                if input.state == ElementState::Pressed {
+                                    state.config = load_config();
+                                    bindings = load_bindings();
+                                    state.start_round();
+                                    soldiers.truncate(1);
+                                    bots.clear();
+
+                                    let soldier = &mut soldiers[0];
+                                    soldier.player = load_player();
+                                    soldier.team =
+                                        Team::balanced(&[], state.config.game_mode.teams());
+                                    soldier.reset_score();
+                                    soldier.respawn(
+                                        state.map.team_spawnpoint(soldier.team),
+                                        state.realistic_mode,
+                                    );
+
+                                    add_bots(&state, &mut soldiers, &mut bots);
+                                }
+                            }
+                            Some(key) if menu.active && input.state == ElementState::Pressed => {
+                                let keys = [
+                                    VirtualKeyCode::Key1,
+                                    VirtualKeyCode::Key2,
+                                    VirtualKeyCode::Key3,
+                                    VirtualKeyCode::Key4,
+                                    VirtualKeyCode::Key5,
+                                    VirtualKeyCode::Key6,
+                                    VirtualKeyCode::Key7,
+                                    VirtualKeyCode::Key8,
+                                    VirtualKeyCode::Key9,
+                                    VirtualKeyCode::Key0,
+                                ];
+
+                                match keys.iter().position(|k| *k == key) {
+                                    Some(i) => {
+                                        if let Some(kind) = menu.key_item(i) {
+                                            request_weapon(&state, &mut soldiers, &mut menu, kind);
+                                        }
+                                    }
+                                    None => bindings.key_input(&input, &mut soldiers[0].control),
+                                }
+                            }
+                            _ => bindings.key_input(&input, &mut soldiers[0].control),
+                        },
                     },
+                    WindowEvent::MouseInput {
+                        state: ElementState::Pressed,
+                        button: MouseButton::Left,
+                        ..
+                    } if menu.active => {
+                        if let Some(kind) = menu.item_at(state.mouse) {
+                            request_weapon(&state, &mut soldiers, &mut menu, kind);
+                        }
+                    }
                     WindowEvent::MouseInput { state, button, .. } => {
-                        soldier.update_mouse_button(&(state, button));
+                        bindings.mouse_input(state, button, &mut soldiers[0].control);
                     }
                     WindowEvent::CursorMoved {
                         position: logical_pos, ..
@@ -159,42 +368,41 @@ fn main() {
             }
         });
 
+        if let Some(name) = console.requested_map.take() {
+            state.map = load_map(&name, cmd.is_present("waypoints"));
+            state.paths = PathFinder::new(&state.map);
+            graphics.load_map(&mut context, &state.map);
+            state.start_round();
+
+            // bots remember routes over the old map's waypoints, so they're replaced
+            soldiers.truncate(1);
+            bots.clear();
+            respawn_all(&state, &mut soldiers);
+            add_bots(&state, &mut soldiers, &mut bots);
+        }
+
         let dt = 1.0 / 60.0;
 
         timecur = current_time();
-        timeacc += timecur - timeprv;
+        timeacc += (timecur - timeprv) * f64::from(state.timescale);
         timeprv = timecur;
 
         while timeacc >= dt {
             timeacc -= dt;
 
-            // remove inactive bullets
-
-            let mut i = 0;
-            while i < state.bullets.len() {
-                if !state.bullets[i].active {
-                    state.bullets.swap_remove(i);
-                } else {
-                    i += 1;
-                }
-            }
-
-            // update soldiers
+            let was_dead = soldiers[0].dead_meat;
 
-            soldier.update(&state, &mut emitter);
+            soldiers[0].control.aim_at_cursor(&state);
+            state.update(&mut soldiers, &mut bots, &mut emitter);
+            log_chat(&mut console, &state.chat, &mut chat_logged);
 
-            // update bullets
-
-            for bullet in state.bullets.iter_mut() {
-                bullet.update(&state.map);
+            if soldiers[0].dead_meat && !was_dead {
+                menu.open();
             }
 
-            // create emitted objects
-
-            for item in emitter.drain(..) {
-                match item {
-                    EmitterItem::Bullet(params) => state.bullets.push(Bullet::new(&params)),
-                };
+            if state.round.finished() {
+                state.start_round();
+                respawn_all(&state, &mut soldiers);
             }
 
             // update camera
@@ -206,6 +414,16 @@ fn main() {
                 state.zoom += iif!(zoomin_pressed, -1.0, 1.0) * dt as f32;
             }
 
+            let soldier = &soldiers[0];
+            let scoped = state.config.barrett().scope
+                && !soldier.dead_meat
+                && soldier.primary_weapon().kind == WeaponKind::Barrett
+                && soldier.crouched_or_prone();
+
+            let scope_step = (iif!(scoped, SCOPE_ZOOM, 0.0) - scope_zoom) * 0.1;
+            scope_zoom += scope_step;
+            state.zoom += scope_step;
+
             state.camera = {
                 let z = f32::exp(state.zoom);
                 let mut m = Vec2::zero();
@@ -215,8 +433,15 @@ fn main() {
                         + (state.game_width - 640.0) / state.game_width * 0.0 / 6.8);
                 m.y = z * (state.mouse.y - state.game_height / 2.0) / 7.0;
 
+                // the scope also looks ahead along the aim
+                let aim = vec2(
+                    soldier.control.mouse_aim_x as f32,
+                    soldier.control.mouse_aim_y as f32,
+                );
+                let look = vec2normalize(aim - soldier.particle.pos) * SCOPE_SHIFT;
+
                 let mut cam_v = state.camera;
-                let p = soldier.particle.pos;
+                let p = soldier.particle.pos + look * (scope_zoom / SCOPE_ZOOM);
                 let norm = p - cam_v;
                 let s = norm * 0.14;
                 cam_v += s;
@@ -225,23 +450,108 @@ fn main() {
             };
 
             timecur = current_time();
-            timeacc += timecur - timeprv;
+            timeacc += (timecur - timeprv) * f64::from(state.timescale);
             timeprv = timecur;
         }
 
         let p = f64::min(1.0, f64::max(0.0, timeacc / dt));
 
+        menu.refresh(&state.config.weapon_limits, &soldiers[0], &soldiers);
+
         graphics.render_frame(
             &mut context,
             &state,
-            &soldier,
+            &soldiers,
+            &menu,
             timecur - dt * (1.0 - p),
             p as f32,
         );
 
+        if show_scores {
+            graphics.render_scoreboard(&mut context, &state, &soldiers);
+        }
+
+        graphics.render_console(&mut context, &state, &console);
         context.present();
 
         // only sleep if no vsync (or if vsync doesn't wait), also needs timeBeginPeriod(1)
         // std::thread::sleep(std::time::Duration::from_millis(1));
     }
 }
+
+// Maps without waypoints get them generated from their geometry.
+fn load_map(name: &str, generate: bool) -> MapFile {
+    let mut map = MapFile::load_map_file(&format!("{}.pms", name));
+
+    if map.waypoints.is_empty() || generate {
+        map.waypoints = generate_waypoints(&map);
+    }
+
+    map
+}
+
+// Chat as the server sees it, after the hooks had their say, goes to the console.
+fn log_chat(console: &mut Console, chat: &Chat, logged: &mut usize) {
+    let new = chat.pushed() - *logged;
+
+    for message in chat.log.iter().skip(chat.log.len().saturating_sub(new)) {
+        match message.sender {
+            Some(_) => console.print(&format!(
+                "[{:?}] {}: {}",
+                message.kind, message.name, message.text
+            )),
+            None => console.print(&message.text),
+        }
+    }
+
+    *logged = chat.pushed();
+}
+
+fn respawn_all(state: &MainState, soldiers: &mut [Soldier]) {
+    for soldier in soldiers.iter_mut() {
+        soldier.reset_score();
+        soldier.respawn(
+            state.map.team_spawnpoint(soldier.team),
+            state.realistic_mode,
+        );
+    }
+}
+
+fn request_weapon(
+    state: &MainState,
+    soldiers: &mut [Soldier],
+    menu: &mut WeaponMenu,
+    kind: WeaponKind,
+) {
+    let request = soldiers[0].loadout.with(kind);
+    let limits = &state.config.weapon_limits;
+
+    if limits.validate(&request, soldiers[0].team, soldiers[0].num, soldiers) {
+        let soldier = &mut soldiers[0];
+        soldier.loadout = request;
+        menu.selection = request;
+
+        if !soldier.dead_meat && !soldier.holds_bow() {
+            soldier.apply_loadout(state.realistic_mode);
+        }
+
+        menu.picked(kind);
+    }
+}
+
+fn add_bots(state: &MainState, soldiers: &mut Vec<Soldier>, bots: &mut Vec<Bot>) {
+    for _ in 0..state.config.bots {
+        let num = soldiers.iter().map(|s| s.num).max().unwrap_or(0) + 1;
+        let team = Team::balanced(soldiers, state.config.game_mode.teams());
+
+        let mut soldier = Soldier::new(state.map.team_spawnpoint(team), state.realistic_mode);
+        soldier.num = num;
+        soldier.team = team;
+        soldier.player.name = format!("Bot {}", bots.len() + 1);
+        soldier.loadout = Bot::choose_loadout(&state.config.weapon_limits, &soldier, soldiers);
+        soldier.apply_loadout(state.realistic_mode);
+
+        soldiers.push(soldier);
+        bots.push(Bot::new(num, state.config.bot_difficulty));
+    }
+}
@@ -1,8 +1,7 @@
 use super::*;
-use byteorder::{LittleEndian, ReadBytesExt};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use std::error::Error;
-use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{self, Read, Seek};
 use std::path::PathBuf;
 
 const MAX_POLYS: i32 = 5000;
@@ -16,6 +15,9 @@ const MAX_SECTOR: i32 = 25;
 const MAX_PROPS: i32 = 500;
 //const MAX_SPAWNPOINTS: i32 = 255;
 //const MAX_COLLIDERS: i32 = 128;
+const MAX_WAYPOINTS: i32 = 5000;
+pub const MAX_CONNECTIONS: usize = 20;
+const SIGHT_STEP: f32 = 4.0;
 
 #[allow(dead_code)]
 #[derive(PartialEq, Debug, Copy, Clone)]
@@ -46,6 +48,30 @@ pub enum PolyType {
     NonFlaggersCollide,
     Background,
     BackgroundTransition,
+    Water,
+}
+
+#[allow(dead_code)]
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum SpawnpointKind {
+    General,
+    Alpha,
+    Bravo,
+    Charlie,
+    Delta,
+    AlphaFlag,
+    BravoFlag,
+    Grenades,
+    Medkits,
+    Clusters,
+    Vest,
+    Flamer,
+    Berserker,
+    Predator,
+    YellowFlag,
+    RamboBow,
+    StationaryGun,
+    Unknown,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -104,10 +130,10 @@ pub struct MapScenery {
 
 #[derive(Debug)]
 pub struct MapCollider {
-    active: bool,
-    x: f32,
-    y: f32,
-    radius: f32,
+    pub active: bool,
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
 }
 
 #[derive(Debug)]
@@ -118,6 +144,25 @@ pub struct MapSpawnpoint {
     pub team: i32,
 }
 
+// Waypoints are the bot navigation graph stored in the map. Connections are indices
+// into `MapFile::waypoints`, the ids the file uses are resolved while loading.
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct MapWaypoint {
+    pub active: bool,
+    pub id: i32,
+    pub x: i32,
+    pub y: i32,
+    pub left: bool,
+    pub right: bool,
+    pub up: bool,
+    pub down: bool,
+    pub jetpack: bool,
+    pub path_num: u8,
+    pub special_action: u8,
+    pub connections: Vec<usize>,
+}
+
 #[allow(dead_code)]
 pub struct MapFile {
     pub filename: String,
@@ -140,11 +185,72 @@ pub struct MapFile {
     pub scenery: Vec<MapScenery>,
     pub colliders: Vec<MapCollider>,
     pub spawnpoints: Vec<MapSpawnpoint>,
+    pub waypoints: Vec<MapWaypoint>,
+    waypoints_offset: u64,
     pub sectors_poly: Vec<Vec<MapSector>>,
     pub perps: Vec<[Vec2; 3]>,
 }
 
+impl MapSpawnpoint {
+    pub fn kind(&self) -> SpawnpointKind {
+        match self.team {
+            0 => SpawnpointKind::General,
+            1 => SpawnpointKind::Alpha,
+            2 => SpawnpointKind::Bravo,
+            3 => SpawnpointKind::Charlie,
+            4 => SpawnpointKind::Delta,
+            5 => SpawnpointKind::AlphaFlag,
+            6 => SpawnpointKind::BravoFlag,
+            7 => SpawnpointKind::Grenades,
+            8 => SpawnpointKind::Medkits,
+            9 => SpawnpointKind::Clusters,
+            10 => SpawnpointKind::Vest,
+            11 => SpawnpointKind::Flamer,
+            12 => SpawnpointKind::Berserker,
+            13 => SpawnpointKind::Predator,
+            14 => SpawnpointKind::YellowFlag,
+            15 => SpawnpointKind::RamboBow,
+            16 => SpawnpointKind::StationaryGun,
+            _ => SpawnpointKind::Unknown,
+        }
+    }
+}
+
+impl SpawnpointKind {
+    pub fn for_team(team: Team) -> SpawnpointKind {
+        match team {
+            Team::None => SpawnpointKind::General,
+            Team::Alpha => SpawnpointKind::Alpha,
+            Team::Bravo => SpawnpointKind::Bravo,
+            Team::Charlie => SpawnpointKind::Charlie,
+            Team::Delta => SpawnpointKind::Delta,
+        }
+    }
+}
+
 impl MapPolygon {
+    pub fn player_collides(&self, team: Team, flagger: bool) -> bool {
+        match self.polytype {
+            PolyType::AlphaPlayers => team == Team::Alpha,
+            PolyType::BravoPlayers => team == Team::Bravo,
+            PolyType::CharliePlayers => team == Team::Charlie,
+            PolyType::DeltaPlayers => team == Team::Delta,
+            PolyType::AlphaBullets => false,
+            PolyType::BravoBullets => false,
+            PolyType::CharlieBullets => false,
+            PolyType::DeltaBullets => false,
+            PolyType::OnlyBulletsCollide => false,
+            PolyType::NoCollide => false,
+            PolyType::OnlyFlaggers => flagger,
+            PolyType::NotFlaggers => !flagger,
+            PolyType::NonFlaggersCollide => false,
+            PolyType::Background => false,
+            PolyType::BackgroundTransition => false,
+            PolyType::Water => false,
+            _ => true,
+        }
+    }
+
     pub fn bullet_collides(&self, team: Team) -> bool {
         match self.polytype {
             PolyType::AlphaBullets => team == Team::Alpha,
@@ -159,11 +265,32 @@ impl MapPolygon {
             PolyType::NoCollide => false,
             PolyType::OnlyFlaggers => false,
             PolyType::NotFlaggers => false,
+            PolyType::NonFlaggersCollide => false,
             PolyType::Background => false,
             PolyType::BackgroundTransition => false,
+            PolyType::Water => false,
             _ => true,
         }
     }
+
+    pub fn thing_collides(&self) -> bool {
+        !matches!(
+            self.polytype,
+            PolyType::OnlyBulletsCollide
+                | PolyType::NoCollide
+                | PolyType::OnlyFlaggers
+                | PolyType::NotFlaggers
+                | PolyType::NonFlaggersCollide
+                | PolyType::Background
+                | PolyType::BackgroundTransition
+                | PolyType::Water
+        )
+    }
+
+    // NonFlaggersCollide is the "flag collides" polygon, a wall only flags bump into.
+    pub fn flag_collides(&self) -> bool {
+        self.polytype == PolyType::NonFlaggersCollide || self.thing_collides()
+    }
 }
 
 impl MapFile {
@@ -171,8 +298,7 @@ impl MapFile {
         let mut path = PathBuf::new();
         path.push("assets/maps/");
         path.push(file_name);
-        let file = File::open(&path).expect("Error opening File");
-        let mut buf = BufReader::new(file);
+        let mut buf = read_asset(&path).expect("Error opening File");
 
         let filename = path.to_string_lossy().into_owned();
         let version = buf.read_i32::<LittleEndian>().unwrap();
@@ -237,6 +363,8 @@ impl MapFile {
                     23 => PolyType::NonFlaggersCollide,
                     24 => PolyType::Background,
                     25 => PolyType::BackgroundTransition,
+                    // not a Soldat polytype, maps made for soldank mark water with it
+                    26 => PolyType::Water,
                     _ => PolyType::Normal,
                 }
             }
@@ -380,6 +508,64 @@ impl MapFile {
             spawnpoints.push(MapSpawnpoint { active, x, y, team });
         }
 
+        // older maps end right after the spawnpoints
+        let waypoints_offset = buf.stream_position().unwrap();
+        let n = buf.read_i32::<LittleEndian>().unwrap_or(0);
+        if !(0..=MAX_WAYPOINTS).contains(&n) {
+            panic!("Wrong PMS data (number of waypoints)");
+        }
+
+        let mut waypoints: Vec<MapWaypoint> = Vec::new();
+        let mut connection_ids: Vec<Vec<i32>> = Vec::new();
+
+        for _i in 0..n {
+            let active = buf.read_u8().unwrap() != 0;
+            let _ = read_filler(&mut buf, 3);
+            let id = buf.read_i32::<LittleEndian>().unwrap();
+            let x = buf.read_i32::<LittleEndian>().unwrap();
+            let y = buf.read_i32::<LittleEndian>().unwrap();
+            let left = buf.read_u8().unwrap() != 0;
+            let right = buf.read_u8().unwrap() != 0;
+            let up = buf.read_u8().unwrap() != 0;
+            let down = buf.read_u8().unwrap() != 0;
+            let jetpack = buf.read_u8().unwrap() != 0;
+            let path_num = buf.read_u8().unwrap();
+            let special_action = buf.read_u8().unwrap();
+            let _ = read_filler(&mut buf, 5);
+            let count = buf.read_i32::<LittleEndian>().unwrap();
+
+            let mut ids = Vec::new();
+            for k in 0..MAX_CONNECTIONS {
+                let id = buf.read_i32::<LittleEndian>().unwrap();
+                if (k as i32) < count {
+                    ids.push(id);
+                }
+            }
+
+            connection_ids.push(ids);
+            waypoints.push(MapWaypoint {
+                active,
+                id,
+                x,
+                y,
+                left,
+                right,
+                up,
+                down,
+                jetpack,
+                path_num,
+                special_action,
+                connections: Vec::new(),
+            });
+        }
+
+        for (i, ids) in connection_ids.iter().enumerate() {
+            waypoints[i].connections = ids
+                .iter()
+                .filter_map(|id| waypoints.iter().position(|w| w.id == *id))
+                .collect();
+        }
+
         MapFile {
             filename,
             version,
@@ -401,11 +587,57 @@ impl MapFile {
             scenery,
             colliders,
             spawnpoints,
+            waypoints,
+            waypoints_offset,
             sectors_poly,
             perps,
         }
     }
 
+    // Waypoints are the last thing in the file, so saving them rewrites the file from
+    // the waypoint count on and leaves everything before untouched.
+    pub fn save_waypoints(&self) -> io::Result<()> {
+        let mut data = std::fs::read(&self.filename)?;
+        data.truncate(self.waypoints_offset as usize);
+        data.write_i32::<LittleEndian>(self.waypoints.len() as i32)?;
+
+        for waypoint in &self.waypoints {
+            let connections: Vec<i32> = waypoint
+                .connections
+                .iter()
+                .take(MAX_CONNECTIONS)
+                .map(|&i| self.waypoints[i].id)
+                .collect();
+
+            data.write_u8(waypoint.active as u8)?;
+            data.extend_from_slice(&[0; 3]);
+            data.write_i32::<LittleEndian>(waypoint.id)?;
+            data.write_i32::<LittleEndian>(waypoint.x)?;
+            data.write_i32::<LittleEndian>(waypoint.y)?;
+
+            for flag in &[
+                waypoint.left,
+                waypoint.right,
+                waypoint.up,
+                waypoint.down,
+                waypoint.jetpack,
+            ] {
+                data.write_u8(*flag as u8)?;
+            }
+
+            data.write_u8(waypoint.path_num)?;
+            data.write_u8(waypoint.special_action)?;
+            data.extend_from_slice(&[0; 5]);
+            data.write_i32::<LittleEndian>(connections.len() as i32)?;
+
+            for k in 0..MAX_CONNECTIONS {
+                data.write_i32::<LittleEndian>(connections.get(k).cloned().unwrap_or(0))?;
+            }
+        }
+
+        std::fs::write(&self.filename, data)
+    }
+
     pub fn point_in_poly(&self, p: Vec2, poly: &MapPolygon) -> bool {
         let a = &poly.vertices[0];
         let b = &poly.vertices[1];
@@ -520,6 +752,54 @@ impl MapFile {
         vec2(0.0f32, 0.0f32)
     }
 
+    // Picks a random spawnpoint of the team, falling back to the general ones and
+    // then to any spawnpoint when the map has none for the team.
+    pub fn team_spawnpoint(&self, team: Team) -> &MapSpawnpoint {
+        let kinds = [SpawnpointKind::for_team(team), SpawnpointKind::General];
+
+        for kind in kinds.iter() {
+            let spawns: Vec<&MapSpawnpoint> = self
+                .spawnpoints
+                .iter()
+                .filter(|spawn| spawn.active && spawn.kind() == *kind)
+                .collect();
+
+            if !spawns.is_empty() {
+                return spawns[rand::random::<usize>() % spawns.len()];
+            }
+        }
+
+        &self.spawnpoints[0]
+    }
+
+    pub fn out_of_bounds(&self, pos: Vec2) -> bool {
+        f32::max(pos.x.abs(), pos.y.abs()) > (self.sectors_num * self.sectors_division - 10) as f32
+    }
+
+    // Walks the segment in small steps and checks that no polygon stopping the team's
+    // bullets is in the way.
+    pub fn line_of_sight(&self, a: Vec2, b: Vec2, team: Team) -> bool {
+        let steps = i32::max(1, f32::ceil(distance(a, b) / SIGHT_STEP) as i32);
+
+        (0..=steps).all(|i| {
+            let p = lerp(a, b, i as f32 / steps as f32);
+
+            !self.sector_polys(p).iter().any(|poly| {
+                let poly = (*poly - 1) as usize;
+                self.polygons[poly].bullet_collides(team)
+                    && self.point_in_poly_edges(p.x, p.y, poly as i32)
+            })
+        })
+    }
+
+    pub fn in_polytype(&self, pos: Vec2, polytype: PolyType) -> bool {
+        self.sector_polys(pos).iter().any(|poly| {
+            let poly = (*poly - 1) as usize;
+            self.polygons[poly].polytype == polytype
+                && self.point_in_poly_edges(pos.x, pos.y, poly as i32)
+        })
+    }
+
     pub fn sector_polys(&self, pos: Vec2) -> &[u16] {
         let num = self.sectors_num;
         let kx = (pos.x / self.sectors_division as f32).round() as i32;
@@ -550,6 +830,12 @@ pub fn read_string<T: Read>(reader: &mut T, length: u32) -> Result<String, Box<d
     Ok(x)
 }
 
+fn read_filler<T: Read>(reader: &mut T, length: usize) -> Result<(), Box<dyn Error>> {
+    let mut buffer = vec![0u8; length];
+    reader.read_exact(buffer.as_mut_slice())?;
+    Ok(())
+}
+
 pub fn read_color<T: Read>(reader: &mut T) -> MapColor {
     let b = reader.read_u8().unwrap();
     let g = reader.read_u8().unwrap();
@@ -1,6 +1,5 @@
 use super::*;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, Cursor};
 use std::path::PathBuf;
 
 // Notes:
@@ -28,6 +27,7 @@ pub struct Constraint {
     pub active: bool,
     pub particle_num: (usize, usize),
     pub rest_length: f32,
+    pub max_stress: Option<f32>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -80,6 +80,7 @@ impl Constraint {
             active: true,
             particle_num: (a_num, b_num),
             rest_length,
+            max_stress: None,
         }
     }
 }
@@ -118,6 +119,26 @@ impl ParticleSystem {
         &self.constraints
     }
 
+    // Stress is the relative stretch of a constraint, constraints given a maximum
+    // break once they get stretched past it.
+    pub fn set_max_stress(&mut self, max_stress: Option<f32>) {
+        for constraint in self.constraints.iter_mut() {
+            constraint.max_stress = max_stress;
+        }
+    }
+
+    pub fn set_gravity(&mut self, gravity: f32) {
+        for particle in self.particles.iter_mut() {
+            particle.gravity = gravity;
+        }
+    }
+
+    pub fn constraint_broken(&self, a_num: usize, b_num: usize) -> bool {
+        self.constraints.iter().any(|c| {
+            !c.active && (c.particle_num == (a_num, b_num) || c.particle_num == (b_num, a_num))
+        })
+    }
+
     pub fn do_verlet_timestep(&mut self) {
         for particle in self.particles.iter_mut() {
             if particle.active {
@@ -128,6 +149,16 @@ impl ParticleSystem {
         self.satisfy_constraints();
     }
 
+    pub fn do_verlet_timestep_of(&mut self, particle_nums: &[usize]) {
+        for num in particle_nums {
+            if self.particles[num - 1].active {
+                self.particles[num - 1].verlet();
+            }
+        }
+
+        self.satisfy_constraints();
+    }
+
     pub fn do_verlet_timestep_for(&mut self, particle_num: usize, constraint_num: usize) {
         self.particles[particle_num - 1].verlet();
         self.satisfy_constraint_for(constraint_num - 1);
@@ -148,9 +179,13 @@ impl ParticleSystem {
     }
 
     pub fn satisfy_constraints(&mut self) {
-        for constraint in self.constraints.iter() {
+        for constraint in self.constraints.iter_mut() {
             if constraint.active {
-                Self::satisfy_constraint(constraint, &mut self.particles);
+                let stress = Self::satisfy_constraint(constraint, &mut self.particles);
+
+                if constraint.max_stress.is_some_and(|max| stress > max) {
+                    constraint.active = false;
+                }
             }
         }
     }
@@ -159,13 +194,18 @@ impl ParticleSystem {
         Self::satisfy_constraint(&self.constraints[constraint_num - 1], &mut self.particles);
     }
 
-    fn satisfy_constraint(constraint: &Constraint, particles: &mut [Particle]) {
+    fn satisfy_constraint(constraint: &Constraint, particles: &mut [Particle]) -> f32 {
         let (a, b) = (constraint.particle_num.0 - 1, constraint.particle_num.1 - 1);
 
         let delta = particles[b].pos - particles[a].pos;
         let length = delta.magnitude();
+        let mut stress = 0.0;
 
         if length > 0.0 {
+            if constraint.rest_length > 0.0 {
+                stress = (length - constraint.rest_length).abs() / constraint.rest_length;
+            }
+
             let diff = (length - constraint.rest_length) / length;
 
             if particles[a].one_over_mass > 0.0 {
@@ -176,6 +216,8 @@ impl ParticleSystem {
                 particles[b].pos -= delta * diff / 2.0;
             }
         }
+
+        stress
     }
 
     pub fn load_from_file(
@@ -189,18 +231,17 @@ impl ParticleSystem {
         let mut path = PathBuf::from("assets/objects/");
         path.push(file_name);
 
-        let file = File::open(&path).expect("Error opening object file.");
+        let mut buf = read_asset(&path).expect("Error opening object file.");
         let mut line = String::new();
-        let mut buf = BufReader::new(file);
         let mut particles: Vec<Particle> = Vec::new();
         let mut constraints: Vec<Constraint> = Vec::new();
 
-        let read_line = |buf: &mut BufReader<File>, line: &mut String| {
+        let read_line = |buf: &mut Cursor<Vec<u8>>, line: &mut String| {
             line.clear();
             buf.read_line(line).ok();
         };
 
-        let read_f32 = |buf: &mut BufReader<File>, line: &mut String| -> f32 {
+        let read_f32 = |buf: &mut Cursor<Vec<u8>>, line: &mut String| -> f32 {
             read_line(buf, line);
             line.trim().parse().unwrap()
         };
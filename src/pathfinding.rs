@@ -0,0 +1,352 @@
+use super::*;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+
+const SUPPORT_DEPTH: f32 = 20.0;
+const SAMPLE_STEP: f32 = 6.0;
+
+// Who is asking for a path and how much detours are worth to them. Costs are in the
+// same unit as distance, so a danger cost of 500 means walking 500 pixels further is
+// as bad as stepping on a hurting polygon once.
+#[derive(Debug, Copy, Clone)]
+pub struct PathQuery {
+    pub team: Team,
+    pub flagger: bool,
+    pub jet_cost: f32,
+    pub danger_cost: f32,
+}
+
+// A path being followed. The front of `waypoints` is the next waypoint to reach.
+#[derive(Debug, Clone)]
+pub struct Route {
+    pub waypoints: VecDeque<usize>,
+    pub goal: usize,
+    blocked: Vec<(usize, usize)>,
+}
+
+// Waypoint graph prepared for path queries. The polygons under every waypoint and
+// along every connection are looked up once, queries then only check them against
+// the team and flag state of the soldier asking.
+pub struct PathFinder {
+    nodes: Vec<Node>,
+}
+
+struct Node {
+    pos: Vec2,
+    active: bool,
+    jetpack: bool,
+    support: Option<MapPolygon>,
+    edges: Vec<Edge>,
+}
+
+struct Edge {
+    to: usize,
+    length: f32,
+    crossed: Vec<MapPolygon>,
+}
+
+#[derive(PartialEq)]
+struct Open {
+    estimate: f32,
+    node: usize,
+}
+
+impl Eq for Open {}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Open) -> Ordering {
+        // reversed, the heap pops the lowest estimate first
+        other
+            .estimate
+            .partial_cmp(&self.estimate)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Open) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PathQuery {
+    pub fn new(team: Team, flagger: bool) -> PathQuery {
+        PathQuery {
+            team,
+            flagger,
+            jet_cost: 1.0,
+            danger_cost: 500.0,
+        }
+    }
+
+    pub fn for_soldier(soldier: &Soldier) -> PathQuery {
+        PathQuery::new(soldier.team, soldier.has_flag)
+    }
+
+    fn deadly(poly: &MapPolygon) -> bool {
+        matches!(
+            poly.polytype,
+            PolyType::Deadly | PolyType::BloodyDeadly | PolyType::Explosive
+        )
+    }
+
+    fn dangerous(&self, poly: &MapPolygon) -> bool {
+        match poly.polytype {
+            PolyType::Lava | PolyType::Hurts => true,
+            PolyType::HurtsFlaggers => self.flagger,
+            _ => false,
+        }
+    }
+}
+
+impl Route {
+    pub fn next(&self) -> Option<usize> {
+        self.waypoints.front().cloned()
+    }
+
+    pub fn advance(&mut self) {
+        self.waypoints.pop_front();
+    }
+
+    pub fn finished(&self) -> bool {
+        self.waypoints.is_empty()
+    }
+}
+
+impl PathFinder {
+    pub fn new(map: &MapFile) -> PathFinder {
+        let pos = |w: &MapWaypoint| vec2(w.x as f32, w.y as f32);
+
+        let nodes = map
+            .waypoints
+            .iter()
+            .map(|w| Node {
+                pos: pos(w),
+                active: w.active,
+                jetpack: w.jetpack,
+                support: Self::support(map, pos(w)),
+                edges: w
+                    .connections
+                    .iter()
+                    .map(|&to| Edge {
+                        to,
+                        length: distance(pos(w), pos(&map.waypoints[to])),
+                        crossed: Self::crossed(map, pos(w), pos(&map.waypoints[to])),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        PathFinder { nodes }
+    }
+
+    // Whether the soldier can stand on the waypoint at all.
+    pub fn usable(&self, node: usize, query: &PathQuery) -> bool {
+        let node = &self.nodes[node];
+
+        node.active
+            && node.support.is_none_or(|poly| {
+                poly.player_collides(query.team, query.flagger) && !PathQuery::deadly(&poly)
+            })
+    }
+
+    pub fn nearest(&self, pos: Vec2, query: &PathQuery) -> Option<usize> {
+        (0..self.nodes.len())
+            .filter(|&i| self.usable(i, query))
+            .min_by(|&a, &b| {
+                let da = distance(pos, self.nodes[a].pos);
+                let db = distance(pos, self.nodes[b].pos);
+                da.partial_cmp(&db).unwrap()
+            })
+    }
+
+    pub fn route(&self, from: usize, to: usize, query: &PathQuery) -> Option<Route> {
+        let blocked = Vec::new();
+
+        self.search(from, &[to], query, &blocked)
+            .map(|waypoints| Route {
+                waypoints: waypoints.into_iter().collect(),
+                goal: to,
+                blocked,
+            })
+    }
+
+    // The connection from `from` to the route's next waypoint turned out to be
+    // impassable. Instead of planning from scratch the route is repaired with the
+    // shortest detour to any waypoint still ahead on it. Returns false when there
+    // is no way around.
+    pub fn replan(&self, route: &mut Route, from: usize, query: &PathQuery) -> bool {
+        if let Some(next) = route.next() {
+            route.blocked.push((from, next));
+        }
+
+        let ahead: Vec<usize> = route.waypoints.iter().cloned().collect();
+        let targets = iif!(ahead.is_empty(), vec![route.goal], ahead.clone());
+
+        let detour = match self.search(from, &targets, query, &route.blocked) {
+            Some(detour) => detour,
+            None => return false,
+        };
+
+        let rejoin = detour.last().cloned().unwrap_or(from);
+        let rest = ahead
+            .iter()
+            .position(|&w| w == rejoin)
+            .map_or(&ahead[0..0], |i| &ahead[i + 1..]);
+
+        route.waypoints = detour.iter().chain(rest).cloned().collect();
+        true
+    }
+
+    fn edge_cost(&self, from: usize, edge: &Edge, query: &PathQuery) -> Option<f32> {
+        if !self.usable(edge.to, query) {
+            return None;
+        }
+
+        let mut walls = edge.crossed.iter();
+
+        if walls.any(|poly| poly.player_collides(query.team, query.flagger)) {
+            return None;
+        }
+
+        let (a, b) = (&self.nodes[from], &self.nodes[edge.to]);
+        let mut cost = edge.length;
+
+        if b.jetpack {
+            cost += query.jet_cost * (a.pos.y - b.pos.y).max(0.0);
+        }
+
+        if b.support.is_some_and(|poly| query.dangerous(&poly)) {
+            cost += query.danger_cost;
+        }
+
+        Some(cost)
+    }
+
+    // A* from one waypoint to the closest of the targets. The returned path leaves
+    // out the starting waypoint.
+    fn search(
+        &self,
+        from: usize,
+        targets: &[usize],
+        query: &PathQuery,
+        blocked: &[(usize, usize)],
+    ) -> Option<Vec<usize>> {
+        let heuristic = |node: usize| {
+            targets
+                .iter()
+                .map(|&t| distance(self.nodes[node].pos, self.nodes[t].pos))
+                .fold(f32::INFINITY, f32::min)
+        };
+
+        let mut cost = vec![f32::INFINITY; self.nodes.len()];
+        let mut previous: Vec<Option<usize>> = vec![None; self.nodes.len()];
+        let mut open = BinaryHeap::new();
+
+        cost[from] = 0.0;
+        open.push(Open {
+            estimate: heuristic(from),
+            node: from,
+        });
+
+        while let Some(Open { node, estimate }) = open.pop() {
+            if estimate > cost[node] + heuristic(node) {
+                continue;
+            }
+
+            if targets.contains(&node) {
+                let mut path = VecDeque::new();
+                let mut i = node;
+
+                while i != from {
+                    path.push_front(i);
+                    i = previous[i].unwrap();
+                }
+
+                return Some(path.into_iter().collect());
+            }
+
+            for edge in &self.nodes[node].edges {
+                if blocked.contains(&(node, edge.to)) {
+                    continue;
+                }
+
+                if let Some(step) = self.edge_cost(node, edge, query) {
+                    let total = cost[node] + step;
+
+                    if total < cost[edge.to] {
+                        cost[edge.to] = total;
+                        previous[edge.to] = Some(node);
+                        open.push(Open {
+                            estimate: total + heuristic(edge.to),
+                            node: edge.to,
+                        });
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    fn polygons_at(map: &MapFile, pos: Vec2) -> impl Iterator<Item = usize> + '_ {
+        map.sector_polys(pos)
+            .iter()
+            .map(|poly| (*poly - 1) as usize)
+            .filter(move |&poly| map.point_in_poly_edges(pos.x, pos.y, poly as i32))
+    }
+
+    // the first polygon below the waypoint that some soldier can stand on
+    fn support(map: &MapFile, pos: Vec2) -> Option<MapPolygon> {
+        let steps = (SUPPORT_DEPTH / 2.0) as i32;
+
+        (1..=steps).find_map(|i| {
+            Self::polygons_at(map, pos + vec2(0.0, i as f32 * 2.0))
+                .map(|poly| map.polygons[poly])
+                .find(|poly| {
+                    !matches!(
+                        poly.polytype,
+                        PolyType::OnlyBulletsCollide
+                            | PolyType::NoCollide
+                            | PolyType::NonFlaggersCollide
+                            | PolyType::Background
+                            | PolyType::BackgroundTransition
+                            | PolyType::Water
+                    )
+                })
+        })
+    }
+
+    // Polygons along a connection that stop some soldiers but not others. Walls that
+    // stop everyone never end up between connected waypoints.
+    fn crossed(map: &MapFile, a: Vec2, b: Vec2) -> Vec<MapPolygon> {
+        let steps = i32::max(1, f32::ceil(distance(a, b) / SAMPLE_STEP) as i32);
+        let mut found: Vec<usize> = Vec::new();
+
+        for i in 0..=steps {
+            let p = lerp(a, b, i as f32 / steps as f32);
+
+            for poly in Self::polygons_at(map, p) {
+                if !found.contains(&poly) {
+                    found.push(poly);
+                }
+            }
+        }
+
+        found
+            .into_iter()
+            .map(|poly| map.polygons[poly])
+            .filter(|poly| {
+                matches!(
+                    poly.polytype,
+                    PolyType::OnlyFlaggers
+                        | PolyType::NotFlaggers
+                        | PolyType::AlphaPlayers
+                        | PolyType::BravoPlayers
+                        | PolyType::CharliePlayers
+                        | PolyType::DeltaPlayers
+                )
+            })
+            .collect()
+    }
+}
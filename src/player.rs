@@ -0,0 +1,114 @@
+use super::*;
+use gfx2d::{rgb, Color};
+use ini::Ini;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum HeadCap {
+    None,
+    Helmet,
+    Hat,
+}
+
+#[derive(Debug, Clone)]
+pub struct Player {
+    pub name: String,
+    pub shirt_color: Color,
+    pub pants_color: Color,
+    pub skin_color: Color,
+    pub hair_color: Color,
+    pub hair_style: u8,
+    pub head_cap: HeadCap,
+    pub chain: u8,
+}
+
+impl Default for Player {
+    fn default() -> Player {
+        Player {
+            name: "Major".to_owned(),
+            shirt_color: rgb(0, 0, 0),
+            pants_color: rgb(0, 0, 0),
+            skin_color: rgb(230, 180, 120),
+            hair_color: rgb(0, 0, 0),
+            hair_style: 3,
+            head_cap: HeadCap::Helmet,
+            chain: 0,
+        }
+    }
+}
+
+impl HeadCap {
+    pub fn sprite(&self) -> Option<gfx::Soldier> {
+        match *self {
+            HeadCap::None => None,
+            HeadCap::Helmet => Some(gfx::Soldier::Helm),
+            HeadCap::Hat => Some(gfx::Soldier::Kap),
+        }
+    }
+}
+
+impl Player {
+    pub fn load_from_file(file_name: &str) -> Player {
+        let mut player = Player::default();
+
+        if let Ok(cfg) = Ini::load_from_file(file_name) {
+            player.load_data(&cfg);
+        }
+
+        player
+    }
+
+    // Reads the [PLAYER] section. Colours are hex RRGGBB values, hair style and chain
+    // are the Soldat numbers (hair: 0 bald, 1 dreadlocks, 2 punk, 3 mr. T, 4 normal;
+    // chain: 0 none, 1 silver, 2 golden).
+    pub fn load_data(&mut self, cfg: &Ini) {
+        if let Some(data) = cfg.section(Some("PLAYER".to_owned())) {
+            if let Some(value) = data.get("Name") {
+                self.name = value.trim().to_owned();
+            }
+
+            for (key, color) in &mut [
+                ("ShirtColor", &mut self.shirt_color),
+                ("PantsColor", &mut self.pants_color),
+                ("SkinColor", &mut self.skin_color),
+                ("HairColor", &mut self.hair_color),
+            ] {
+                if let Some(value) = data.get(*key).and_then(|v| parse_color(v)) {
+                    **color = value;
+                }
+            }
+
+            if let Some(Ok(value)) = data.get("HairStyle").map(|v| v.trim().parse::<u8>()) {
+                self.hair_style = u8::min(value, 4);
+            }
+
+            if let Some(value) = data.get("HeadCap") {
+                self.head_cap = match value.trim().to_lowercase().as_str() {
+                    "none" => HeadCap::None,
+                    "hat" => HeadCap::Hat,
+                    _ => HeadCap::Helmet,
+                };
+            }
+
+            if let Some(Ok(value)) = data.get("Chain").map(|v| v.trim().parse::<u8>()) {
+                self.chain = u8::min(value, 2);
+            }
+        }
+    }
+
+    // Team games override the shirt so players can tell the sides apart.
+    pub fn shirt_color(&self, team: Team) -> Color {
+        team.color().unwrap_or(self.shirt_color)
+    }
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    let value = value.trim().trim_start_matches('#');
+
+    if value.len() != 6 {
+        return None;
+    }
+
+    u32::from_str_radix(value, 16)
+        .ok()
+        .map(|c| rgb((c >> 16) as u8, (c >> 8) as u8, c as u8))
+}
@@ -38,6 +38,29 @@ pub fn render_bullet(
                 },
             );
         }
+        BulletStyle::Arrow | BulletStyle::FlameArrow => {
+            let sprite = gfx::Weapon::Arrow;
+            let sprite = &sprites[sprite.group().id()][sprite.id()];
+
+            batch.add_sprite(
+                sprite,
+                rgb(255, 255, 255),
+                Transform::WithPivot {
+                    pivot: vec2(sprite.width, 0.5 * sprite.height),
+                    pos,
+                    scale: vec2(1.0, 1.0),
+                    rot: vec2angle(bullet.particle.velocity),
+                },
+            );
+
+            if bullet.style == BulletStyle::FlameArrow {
+                render_flame(pos, 255, sprites, batch);
+            }
+        }
+        BulletStyle::Flame => {
+            let alpha = f32::min(255.0, 255.0 * bullet.timeout as f32 / 30.0);
+            render_flame(pos, alpha as u8, sprites, batch);
+        }
         _ => {
             if let Some(sprite) = bullet.sprite {
                 let sprite = &sprites[sprite.group().id()][sprite.id()];
@@ -70,3 +93,14 @@ pub fn render_bullet(
         }
     };
 }
+
+pub fn render_flame(pos: Vec2, alpha: u8, sprites: &[Vec<Sprite>], batch: &mut DrawBatch) {
+    let sprite = gfx::Spark::Lilfire;
+    let sprite = &sprites[sprite.group().id()][sprite.id()];
+
+    batch.add_sprite(
+        sprite,
+        rgba(255, 255, 255, alpha),
+        Transform::Pos(pos - vec2(0.5 * sprite.width, sprite.height)),
+    );
+}
@@ -0,0 +1,97 @@
+use super::*;
+
+const LOG_LINES: usize = 6;
+const FADE_TIME: i32 = 60;
+
+fn message_color(message: &ChatMessage) -> Color {
+    match message.kind {
+        ChatKind::All => rgb(255, 255, 255),
+        ChatKind::Team => rgb(230, 230, 120),
+        ChatKind::Radio => rgb(160, 220, 160),
+        ChatKind::Server => rgb(255, 120, 120),
+    }
+}
+
+fn message_text(message: &ChatMessage) -> String {
+    match message.kind {
+        ChatKind::All => format!("{}: {}", message.name, message.text),
+        ChatKind::Team => format!("(Team) {}: {}", message.name, message.text),
+        ChatKind::Radio => format!("(Radio) {}: {}", message.name, message.text),
+        ChatKind::Server => format!("*** {}", message.text),
+    }
+}
+
+// Recent messages top left, the input box above the HUD and the radio menu on the
+// left edge, in screen space.
+pub fn render_chat(state: &MainState, soldiers: &[Soldier], font: &Font, batch: &mut DrawBatch) {
+    let chat = &state.chat;
+    let player = soldiers.first();
+    let line_height = font.line_height();
+
+    let recent: Vec<&ChatMessage> = chat
+        .log
+        .iter()
+        .rev()
+        .filter(|m| player.is_some_and(|p| m.visible_to(p)))
+        .filter(|m| chat.time() - m.time < MESSAGE_TIME)
+        .take(LOG_LINES)
+        .collect();
+
+    for (i, message) in recent.iter().rev().enumerate() {
+        let alpha = (MESSAGE_TIME - (chat.time() - message.time)).min(FADE_TIME);
+        let alpha = (255 * alpha / FADE_TIME) as u8;
+        let mut color = message_color(message);
+        color.set_a(alpha);
+
+        let style = TextStyle::new(color).shadow(rgba(0, 0, 0, alpha));
+        let pos = vec2(8.0, 8.0 + i as f32 * line_height);
+        batch.add_text(font, &message_text(message), pos, &style);
+    }
+
+    if let Some((kind, ref text)) = chat.input {
+        let prompt = iif!(kind == ChatKind::Team, "Say team", "Say");
+        let style = TextStyle::new(rgb(255, 255, 255)).shadow(rgb(0, 0, 0));
+        let pos = vec2(8.0, state.game_height - 80.0);
+        batch.add_text(font, &format!("{}: {}_", prompt, text), pos, &style);
+    }
+
+    if chat.radio_menu {
+        let style = TextStyle::new(rgb(160, 220, 160)).shadow(rgb(0, 0, 0));
+        let top = 0.5 * (state.game_height - RADIO.len() as f32 * line_height);
+
+        for (i, call) in RADIO.iter().enumerate() {
+            let pos = vec2(8.0, top + i as f32 * line_height);
+            batch.add_text(font, &format!("{}. {}", i + 1, call), pos, &style);
+        }
+    }
+}
+
+// The last thing each soldier said, over their head in world space.
+pub fn render_chat_bubbles(
+    state: &MainState,
+    soldiers: &[Soldier],
+    font: &Font,
+    batch: &mut DrawBatch,
+    frame_percent: f32,
+) {
+    let player = match soldiers.first() {
+        Some(player) => player,
+        None => return,
+    };
+
+    for soldier in soldiers.iter().filter(|s| s.active) {
+        let message = match state.chat.bubble(soldier.num) {
+            Some(message) if message.visible_to(player) => message,
+            _ => continue,
+        };
+
+        let sk = &soldier.skeleton;
+        let head = lerp(sk.old_pos(12), sk.pos(12), frame_percent);
+        let pos = head - vec2(0.0, 16.0 + font.line_height());
+        let style = TextStyle::new(message_color(message))
+            .align(Align::Center)
+            .shadow(rgb(0, 0, 0));
+
+        batch.add_text(font, &message.text, pos, &style);
+    }
+}
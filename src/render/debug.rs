@@ -0,0 +1,317 @@
+use super::*;
+
+const TRACE_TIME: i32 = 120;
+const CIRCLE_SEGMENTS: usize = 24;
+
+// Debug drawing switched on from the console.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct DebugOptions {
+    pub skeleton: bool,
+    pub polygons: bool,
+    pub sectors: bool,
+    pub bullets: bool,
+    pub colliders: bool,
+    pub waypoints: bool,
+    pub routes: bool,
+    pub hit_zones: bool,
+}
+
+// Recent bullet movement for the bullet overlay. Bullets are gone by the time they're
+// interesting to look at, so their steps and impacts are kept around for a while.
+#[derive(Debug, Default)]
+pub struct BulletTraces {
+    segments: Vec<(Vec2, Vec2, i32)>,
+    impacts: Vec<(Vec2, i32)>,
+}
+
+impl DebugOptions {
+    pub fn any(&self) -> bool {
+        self.skeleton
+            || self.polygons
+            || self.sectors
+            || self.bullets
+            || self.colliders
+            || self.waypoints
+            || self.routes
+            || self.hit_zones
+    }
+}
+
+impl BulletTraces {
+    pub fn add_segment(&mut self, a: Vec2, b: Vec2) {
+        if a != b {
+            self.segments.push((a, b, TRACE_TIME));
+        }
+    }
+
+    pub fn add_impact(&mut self, pos: Vec2) {
+        self.impacts.push((pos, TRACE_TIME));
+    }
+
+    pub fn update(&mut self) {
+        self.segments.iter_mut().for_each(|s| s.2 -= 1);
+        self.impacts.iter_mut().for_each(|i| i.1 -= 1);
+        self.segments.retain(|s| s.2 > 0);
+        self.impacts.retain(|i| i.1 > 0);
+    }
+}
+
+fn poly_color(polytype: PolyType) -> Color {
+    match polytype {
+        PolyType::Normal => rgb(255, 255, 255),
+        PolyType::OnlyBulletsCollide => rgb(255, 160, 0),
+        PolyType::OnlyPlayersCollide => rgb(0, 255, 255),
+        PolyType::NoCollide => rgb(100, 100, 100),
+        PolyType::Ice => rgb(160, 220, 255),
+        PolyType::Deadly | PolyType::BloodyDeadly => rgb(255, 0, 0),
+        PolyType::Hurts | PolyType::HurtsFlaggers => rgb(255, 100, 100),
+        PolyType::Regenerates => rgb(100, 255, 100),
+        PolyType::Lava => rgb(255, 80, 0),
+        PolyType::AlphaBullets | PolyType::AlphaPlayers => Team::Alpha.color().unwrap(),
+        PolyType::BravoBullets | PolyType::BravoPlayers => Team::Bravo.color().unwrap(),
+        PolyType::CharlieBullets | PolyType::CharliePlayers => Team::Charlie.color().unwrap(),
+        PolyType::DeltaBullets | PolyType::DeltaPlayers => Team::Delta.color().unwrap(),
+        PolyType::Bouncy => rgb(255, 0, 255),
+        PolyType::Explosive => rgb(255, 255, 0),
+        PolyType::OnlyFlaggers | PolyType::NotFlaggers | PolyType::NonFlaggersCollide => {
+            rgb(200, 150, 255)
+        }
+        PolyType::Background | PolyType::BackgroundTransition => rgb(60, 60, 60),
+        PolyType::Water => rgb(40, 100, 255),
+    }
+}
+
+fn spawnpoint_color(kind: SpawnpointKind) -> Color {
+    match kind {
+        SpawnpointKind::Alpha | SpawnpointKind::AlphaFlag => Team::Alpha.color().unwrap(),
+        SpawnpointKind::Bravo | SpawnpointKind::BravoFlag => Team::Bravo.color().unwrap(),
+        SpawnpointKind::Charlie => Team::Charlie.color().unwrap(),
+        SpawnpointKind::Delta => Team::Delta.color().unwrap(),
+        SpawnpointKind::YellowFlag => rgb(255, 255, 0),
+        SpawnpointKind::General => rgb(255, 255, 255),
+        _ => rgb(0, 255, 0),
+    }
+}
+
+fn line(batch: &mut DrawBatch, a: Vec2, b: Vec2, width: f32, color: Color) {
+    let length = distance(a, b);
+
+    if length == 0.0 {
+        return;
+    }
+
+    let n = vec2(a.y - b.y, b.x - a.x) * (0.5 * width / length);
+
+    batch.add_quad(
+        None,
+        &[
+            vertex(a + n, Vec2::zero(), color),
+            vertex(b + n, Vec2::zero(), color),
+            vertex(b - n, Vec2::zero(), color),
+            vertex(a - n, Vec2::zero(), color),
+        ],
+    );
+}
+
+fn circle(batch: &mut DrawBatch, center: Vec2, radius: f32, width: f32, color: Color) {
+    let point = |i: usize| {
+        let angle = i as f32 / CIRCLE_SEGMENTS as f32 * 2.0 * ::std::f32::consts::PI;
+        center + vec2(angle.cos(), angle.sin()) * radius
+    };
+
+    for i in 0..CIRCLE_SEGMENTS {
+        line(batch, point(i), point(i + 1), width, color);
+    }
+}
+
+fn dot(batch: &mut DrawBatch, pos: Vec2, size: f32, color: Color) {
+    let h = 0.5 * size;
+
+    batch.add_quad(
+        None,
+        &[
+            vertex(pos + vec2(-h, -h), Vec2::zero(), color),
+            vertex(pos + vec2(h, -h), Vec2::zero(), color),
+            vertex(pos + vec2(h, h), Vec2::zero(), color),
+            vertex(pos + vec2(-h, h), Vec2::zero(), color),
+        ],
+    );
+}
+
+// Draws the enabled overlays in world space, `px` is the size of a screen pixel.
+pub fn render_debug(
+    state: &MainState,
+    soldiers: &[Soldier],
+    batch: &mut DrawBatch,
+    px: f32,
+    frame_percent: f32,
+) {
+    let map = &state.map;
+    let debug = &state.debug;
+
+    if debug.sectors {
+        let div = map.sectors_division as f32;
+        let num = map.sectors_num as f32;
+        let (min, max) = ((-num - 0.5) * div, (num + 0.5) * div);
+        let color = rgba(0, 120, 255, 120);
+
+        for k in 0..=2 * map.sectors_num + 1 {
+            let x = min + k as f32 * div;
+            line(batch, vec2(x, min), vec2(x, max), px, color);
+            line(batch, vec2(min, x), vec2(max, x), px, color);
+        }
+
+        // the sector the local player is in, soldiers only collide with these
+        if let Some(soldier) = soldiers.first() {
+            let pos = soldier.particle.pos;
+            let center = vec2((pos.x / div).round(), (pos.y / div).round()) * div;
+            let h = 0.5 * div;
+            let corners = [
+                center + vec2(-h, -h),
+                center + vec2(h, -h),
+                center + vec2(h, h),
+                center + vec2(-h, h),
+            ];
+
+            for k in 0..4 {
+                line(
+                    batch,
+                    corners[k],
+                    corners[(k + 1) % 4],
+                    2.0 * px,
+                    rgb(0, 160, 255),
+                );
+            }
+
+            for poly in map.sector_polys(pos) {
+                let v = &map.polygons[(*poly - 1) as usize].vertices;
+                let color = rgba(0, 160, 255, 80);
+
+                batch.add_quad(
+                    None,
+                    &[
+                        vertex(vec2(v[0].x, v[0].y), Vec2::zero(), color),
+                        vertex(vec2(v[1].x, v[1].y), Vec2::zero(), color),
+                        vertex(vec2(v[2].x, v[2].y), Vec2::zero(), color),
+                        vertex(vec2(v[2].x, v[2].y), Vec2::zero(), color),
+                    ],
+                );
+            }
+        }
+    }
+
+    if debug.polygons {
+        for poly in &map.polygons {
+            let color = poly_color(poly.polytype);
+            let v = &poly.vertices;
+
+            for k in 0..3 {
+                let (a, b) = (&v[k], &v[(k + 1) % 3]);
+                line(batch, vec2(a.x, a.y), vec2(b.x, b.y), px, color);
+            }
+        }
+    }
+
+    if debug.colliders {
+        for collider in map.colliders.iter().filter(|c| c.active) {
+            let pos = vec2(collider.x, collider.y);
+            circle(batch, pos, collider.radius, px, rgb(255, 128, 0));
+        }
+
+        for spawn in map.spawnpoints.iter().filter(|s| s.active) {
+            let pos = vec2(spawn.x as f32, spawn.y as f32);
+            let color = spawnpoint_color(spawn.kind());
+            dot(batch, pos, 6.0 * px, color);
+            circle(batch, pos, 8.0 * px, px, color);
+        }
+    }
+
+    if debug.waypoints {
+        for waypoint in &map.waypoints {
+            let pos = vec2(waypoint.x as f32, waypoint.y as f32);
+
+            for &to in &waypoint.connections {
+                let other = &map.waypoints[to];
+                let end = vec2(other.x as f32, other.y as f32);
+                let color = iif!(other.jetpack, rgba(255, 140, 0, 140), rgba(0, 255, 0, 140));
+
+                // drawn to the middle only, the other half belongs to the way back
+                line(batch, pos, lerp(pos, end, 0.5), px, color);
+            }
+
+            let color = iif!(waypoint.active, rgb(0, 255, 0), rgb(120, 120, 120));
+            dot(batch, pos, 4.0 * px, color);
+        }
+    }
+
+    // the way a bot in the local player's shoes would take to the cursor
+    if debug.routes {
+        if let Some(soldier) = soldiers.first() {
+            let query = PathQuery::for_soldier(soldier);
+            let aim = vec2(
+                soldier.control.mouse_aim_x as f32,
+                soldier.control.mouse_aim_y as f32,
+            );
+
+            let route = match (
+                state.paths.nearest(soldier.particle.pos, &query),
+                state.paths.nearest(aim, &query),
+            ) {
+                (Some(from), Some(to)) => state.paths.route(from, to, &query),
+                _ => None,
+            };
+
+            if let Some(route) = route {
+                let color = soldier.team.color().unwrap_or_else(|| rgb(255, 255, 255));
+                let waypoint_pos = |i: usize| {
+                    let waypoint = &map.waypoints[i];
+                    vec2(waypoint.x as f32, waypoint.y as f32)
+                };
+
+                let mut prev = soldier.particle.pos;
+
+                for &i in &route.waypoints {
+                    let pos = waypoint_pos(i);
+                    let color = iif!(map.waypoints[i].jetpack, rgb(255, 140, 0), color);
+                    line(batch, prev, pos, 2.0 * px, color);
+                    dot(batch, pos, 6.0 * px, color);
+                    prev = pos;
+                }
+
+                circle(batch, waypoint_pos(route.goal), 8.0 * px, px, color);
+            }
+        }
+    }
+
+    if debug.bullets {
+        let traces = &state.bullet_traces;
+
+        for &(a, b, time) in &traces.segments {
+            let alpha = (255 * time / TRACE_TIME) as u8;
+            line(batch, a, b, px, rgba(255, 255, 0, alpha));
+        }
+
+        for &(pos, time) in &traces.impacts {
+            let alpha = (255 * time / TRACE_TIME) as u8;
+            dot(batch, pos, 4.0 * px, rgba(255, 0, 0, alpha));
+        }
+    }
+
+    for soldier in soldiers.iter().filter(|s| s.active) {
+        if debug.skeleton {
+            render_skeleton(soldier, batch, px, frame_percent);
+        }
+
+        // where bullet hits are checked, drawn at the last tick's positions
+        if debug.hit_zones {
+            circle(batch, soldier.body_center(), HIT_RADIUS, px, rgb(255, 0, 0));
+            circle(
+                batch,
+                soldier.skeleton.pos(12),
+                HEADSHOT_RADIUS,
+                px,
+                rgb(255, 0, 255),
+            );
+        }
+    }
+}
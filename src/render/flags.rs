@@ -0,0 +1,42 @@
+use super::*;
+use gfx::SpriteData;
+
+pub fn render_flag(
+    flag: &Flag,
+    sprites: &[Vec<Sprite>],
+    batch: &mut DrawBatch,
+    frame_percent: f32,
+) {
+    let sk = &flag.skeleton;
+    let pos = |i: usize| lerp(sk.old_pos(i), sk.pos(i), frame_percent);
+    let (bottom, top) = (pos(1), pos(2));
+    let color = flag.team.color().unwrap_or_else(|| rgb(255, 255, 0));
+
+    let handle = &sprites[gfx::Object::FlagHandle.group().id()][gfx::Object::FlagHandle.id()];
+
+    batch.add_sprite(
+        handle,
+        rgb(255, 255, 255),
+        Transform::WithPivot {
+            pivot: vec2(0.0, 0.5 * handle.height),
+            pos: bottom,
+            scale: vec2(distance(bottom, top) / handle.width, 1.0),
+            rot: vec2angle(top - bottom),
+        },
+    );
+
+    // the cloth is stretched between the upper half of the pole and its two free corners
+    let cloth = &sprites[gfx::Object::Flag.group().id()][gfx::Object::Flag.id()];
+    let (u0, u1) = cloth.texcoords_x;
+    let (v0, v1) = cloth.texcoords_y;
+
+    batch.add_quad(
+        cloth.texture.as_ref(),
+        &[
+            vertex(top, vec2(u0, v0), color),
+            vertex(pos(3), vec2(u1, v0), color),
+            vertex(pos(4), vec2(u1, v1), color),
+            vertex(lerp(bottom, top, 0.5), vec2(u0, v1), color),
+        ],
+    );
+}
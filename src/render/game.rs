@@ -6,6 +6,8 @@ use std::str::FromStr;
 pub struct GameGraphics {
     map: MapGraphics,
     soldier_graphics: SoldierGraphics,
+    hud: HudLayout,
+    font: Font,
     sprites: Vec<Vec<Sprite>>,
     batch: DrawBatch,
 }
@@ -15,6 +17,8 @@ impl GameGraphics {
         GameGraphics {
             map: MapGraphics::empty(),
             soldier_graphics: SoldierGraphics::new(),
+            hud: HudLayout::default(),
+            font: Font::empty(),
             sprites: Vec::new(),
             batch: DrawBatch::new(),
         }
@@ -24,7 +28,8 @@ impl GameGraphics {
         &mut self,
         context: &mut Gfx2dContext,
         state: &MainState,
-        soldier: &Soldier,
+        soldiers: &[Soldier],
+        menu: &WeaponMenu,
         elapsed: f64,
         frame_percent: f32,
     ) {
@@ -37,17 +42,27 @@ impl GameGraphics {
 
         self.batch.clear();
 
-        render_soldier(
-            soldier,
-            &self.soldier_graphics,
-            &self.sprites,
-            &mut self.batch,
-            frame_percent,
-        );
+        for soldier in soldiers.iter().filter(|s| s.active) {
+            render_soldier(
+                soldier,
+                &self.soldier_graphics,
+                &self.sprites,
+                &mut self.batch,
+                frame_percent,
+                state.realistic_mode,
+            );
+        }
+
+        for gun in &state.stationary_guns {
+            render_stationary_gun(gun, &self.sprites, &mut self.batch, frame_percent);
+        }
 
-        if false {
-            let px = h / context.wnd.window().get_inner_size().unwrap().to_physical(1.).height as f32;
-            render_skeleton(soldier, &mut self.batch, px, frame_percent);
+        for thing in &state.things {
+            render_thing(thing, &self.sprites, &mut self.batch, frame_percent);
+        }
+
+        for flag in &state.flags {
+            render_flag(flag, &self.sprites, &mut self.batch, frame_percent);
         }
 
         for bullet in &state.bullets {
@@ -68,9 +83,207 @@ impl GameGraphics {
         context.draw(&mut self.map.scenery_mid(), &transform);
         context.draw(&mut self.map.polys_front(), &transform);
         context.draw(&mut self.map.scenery_front(), &transform);
+
+        if state.debug.any() {
+            let px = h / context
+                .wnd
+                .window()
+                .get_inner_size()
+                .unwrap()
+                .to_physical(1.)
+                .height as f32;
+
+            self.batch.clear();
+            render_debug(state, soldiers, &mut self.batch, px, frame_percent);
+            context.draw(&mut self.batch.all(), &transform);
+        }
+
+        self.batch.clear();
+        render_chat_bubbles(state, soldiers, &self.font, &mut self.batch, frame_percent);
+        context.draw(&mut self.batch.all(), &transform);
+
+        if let Some(soldier) = soldiers.first().filter(|s| s.active && !s.dead_meat) {
+            self.render_hud(context, state, soldier);
+        }
+
+        self.render_kill_feed(context, state, soldiers);
+        self.render_chat(context, state, soldiers);
+
+        if menu.active {
+            self.render_weapon_menu(context, state, menu);
+        }
+
         self.render_cursor(context, state);
     }
 
+    fn render_hud(&mut self, context: &mut Gfx2dContext, state: &MainState, soldier: &Soldier) {
+        let screen = Transform::ortho(0.0, state.game_width, 0.0, state.game_height).matrix();
+
+        self.batch.clear();
+        render_hud(
+            soldier,
+            state,
+            &self.hud,
+            &self.sprites,
+            &self.font,
+            &mut self.batch,
+        );
+        context.draw(&mut self.batch.all(), &screen);
+    }
+
+    fn render_kill_feed(
+        &mut self,
+        context: &mut Gfx2dContext,
+        state: &MainState,
+        soldiers: &[Soldier],
+    ) {
+        let screen = Transform::ortho(0.0, state.game_width, 0.0, state.game_height).matrix();
+
+        self.batch.clear();
+        render_kill_feed(state, soldiers, &self.sprites, &self.font, &mut self.batch);
+        context.draw(&mut self.batch.all(), &screen);
+    }
+
+    fn render_chat(&mut self, context: &mut Gfx2dContext, state: &MainState, soldiers: &[Soldier]) {
+        let screen = Transform::ortho(0.0, state.game_width, 0.0, state.game_height).matrix();
+
+        self.batch.clear();
+        render_chat(state, soldiers, &self.font, &mut self.batch);
+        context.draw(&mut self.batch.all(), &screen);
+    }
+
+    pub fn render_scoreboard(
+        &mut self,
+        context: &mut Gfx2dContext,
+        state: &MainState,
+        soldiers: &[Soldier],
+    ) {
+        let screen = Transform::ortho(0.0, state.game_width, 0.0, state.game_height).matrix();
+
+        self.batch.clear();
+        render_scoreboard(state, soldiers, &self.sprites, &self.font, &mut self.batch);
+        context.draw(&mut self.batch.all(), &screen);
+    }
+
+    fn render_weapon_menu(
+        &mut self,
+        context: &mut Gfx2dContext,
+        state: &MainState,
+        menu: &WeaponMenu,
+    ) {
+        let screen = Transform::ortho(0.0, state.game_width, 0.0, state.game_height).matrix();
+        let size = WeaponMenu::item_size();
+
+        self.batch.clear();
+
+        for kind in Loadout::primaries().iter().chain(Loadout::secondaries()) {
+            let pos = WeaponMenu::item_pos(*kind);
+            let selected = menu.selection.primary == *kind || menu.selection.secondary == *kind;
+            let hovered = menu.item_at(state.mouse) == Some(*kind);
+            let picking = kind.group() == menu.picking;
+
+            let back = match (selected, hovered, picking) {
+                (true, _, _) => rgba(60, 90, 60, 200),
+                (false, true, _) => rgba(60, 60, 60, 200),
+                (false, false, true) => rgba(35, 35, 35, 180),
+                (false, false, false) => rgba(20, 20, 20, 160),
+            };
+
+            self.batch.add_quad(
+                None,
+                &[
+                    vertex(pos, Vec2::zero(), back),
+                    vertex(pos + vec2(size.x, 0.0), Vec2::zero(), back),
+                    vertex(pos + size, Vec2::zero(), back),
+                    vertex(pos + vec2(0.0, size.y), Vec2::zero(), back),
+                ],
+            );
+
+            let color = iif!(
+                menu.available[kind.index()],
+                rgb(255, 255, 255),
+                rgba(255, 80, 80, 80)
+            );
+
+            let icon = gfx::Interface::GunsDeagles + kind.index();
+            let sprite = &self.sprites[icon.group().id()][icon.id()];
+
+            self.batch.add_sprite(
+                sprite,
+                color,
+                Transform::Pos(pos + vec2(4.0, 0.5 * (size.y - sprite.height))),
+            );
+        }
+
+        context.draw(&mut self.batch.all(), &screen);
+    }
+
+    // drawn over everything else, the cursor included
+    pub fn render_console(
+        &mut self,
+        context: &mut Gfx2dContext,
+        state: &MainState,
+        console: &Console,
+    ) {
+        if !console.active {
+            return;
+        }
+
+        let screen = Transform::ortho(0.0, state.game_width, 0.0, state.game_height).matrix();
+        let (w, h) = (state.game_width, 0.4 * state.game_height);
+        let back = rgba(10, 10, 20, 200);
+        let line = rgba(60, 60, 80, 220);
+
+        self.batch.clear();
+
+        self.batch.add_quad(
+            None,
+            &[
+                vertex(vec2(0.0, 0.0), Vec2::zero(), back),
+                vertex(vec2(w, 0.0), Vec2::zero(), back),
+                vertex(vec2(w, h), Vec2::zero(), back),
+                vertex(vec2(0.0, h), Vec2::zero(), back),
+            ],
+        );
+
+        self.batch.add_quad(
+            None,
+            &[
+                vertex(vec2(0.0, h - 1.0), Vec2::zero(), line),
+                vertex(vec2(w, h - 1.0), Vec2::zero(), line),
+                vertex(vec2(w, h), Vec2::zero(), line),
+                vertex(vec2(0.0, h), Vec2::zero(), line),
+            ],
+        );
+
+        // newest lines at the bottom, right above the input line
+        let line_height = self.font.line_height();
+
+        if line_height > 0.0 {
+            let style = TextStyle::new(rgb(200, 200, 200));
+            let mut y = h - 4.0 - line_height;
+
+            self.batch.add_text(
+                &self.font,
+                &format!("] {}_", console.input),
+                vec2(4.0, y),
+                &TextStyle::new(rgb(255, 255, 255)),
+            );
+
+            for line in console.lines.iter().rev() {
+                y -= line_height;
+
+                if y < 0.0 {
+                    break;
+                }
+
+                self.batch.add_text(&self.font, line, vec2(4.0, y), &style);
+            }
+        }
+
+        context.draw(&mut self.batch.all(), &screen);
+    }
+
     fn render_cursor(&mut self, context: &mut Gfx2dContext, state: &MainState) {
         let zoom = f32::exp(state.zoom);
         let (w, h) = (zoom * state.game_width, zoom * state.game_height);
@@ -105,6 +318,26 @@ impl GameGraphics {
         context.draw(&mut self.batch.all(), &screen);
     }
 
+    // Rasterized for the current window size so text stays sharp, falls back to a bitmap
+    // font and then to no text at all.
+    pub fn load_font(&mut self, context: &mut Gfx2dContext, state: &MainState) {
+        let size = context
+            .wnd
+            .window()
+            .get_inner_size()
+            .unwrap()
+            .to_physical(1.);
+        let ratio = size.height as f32 / state.game_height;
+        let pixel_ratio = vec2(ratio, ratio);
+
+        self.font = Font::from_ttf(context, "assets/fonts/play-regular.ttf", 11.0, pixel_ratio)
+            .or_else(|| {
+                let fname = filename_override("assets/", "fonts/font.bmp");
+                Font::from_bitmap(context, fname, Some(rgb(0, 255, 0)), vec2(1.0, 1.0))
+            })
+            .unwrap_or_else(Font::empty);
+    }
+
     pub fn load_map(&mut self, context: &mut Gfx2dContext, map: &MapFile) {
         self.map = MapGraphics::new(context, map);
     }
@@ -149,6 +382,7 @@ impl GameGraphics {
 
         if let Ok(cfg) = Ini::load_from_file("assets/mod.ini") {
             self.soldier_graphics.load_data(&cfg);
+            self.hud.load_data(&cfg);
 
             if let Some(data) = cfg.section(Some("SCALE".to_owned())) {
                 let default_scale = match data.get("DefaultScale") {
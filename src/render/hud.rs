@@ -0,0 +1,253 @@
+use super::*;
+use gfx::SpriteData;
+use ini::Ini;
+use std::str::FromStr;
+
+const SCREEN_WIDTH: f32 = 640.0;
+
+#[derive(Debug, Copy, Clone)]
+struct Bar {
+    pos: Vec2,
+    size: Vec2,
+}
+
+// Where the HUD elements go on Soldat's 640x480 screen, wider screens keep the layout
+// centered. Interfaces override these in the [INTERFACE] section of mod.ini with the
+// names Soldat interfaces use, e.g. `HealthBar_X` or `Nades_Width`.
+pub struct HudLayout {
+    alpha: u8,
+    health_icon: Vec2,
+    health_bar: Bar,
+    vest_bar: Bar,
+    ammo_icon: Vec2,
+    reload_bar: Bar,
+    ammo_count: Vec2,
+    weapon: Vec2,
+    weapon_name: Vec2,
+    jet_icon: Vec2,
+    jet_bar: Bar,
+    nades: Vec2,
+    nades_spacing: f32,
+}
+
+impl Bar {
+    fn new(x: f32, y: f32, width: f32, height: f32) -> Bar {
+        Bar {
+            pos: vec2(x, y),
+            size: vec2(width, height),
+        }
+    }
+}
+
+impl Default for HudLayout {
+    fn default() -> HudLayout {
+        HudLayout {
+            alpha: 255,
+            health_icon: vec2(5.0, 445.0),
+            health_bar: Bar::new(45.0, 455.0, 115.0, 9.0),
+            vest_bar: Bar::new(45.0, 466.0, 115.0, 5.0),
+            ammo_icon: vec2(235.0, 445.0),
+            reload_bar: Bar::new(275.0, 455.0, 120.0, 9.0),
+            ammo_count: vec2(400.0, 452.0),
+            weapon: vec2(275.0, 425.0),
+            weapon_name: vec2(395.0, 428.0),
+            jet_icon: vec2(480.0, 445.0),
+            jet_bar: Bar::new(519.0, 455.0, 115.0, 9.0),
+            nades: vec2(275.0, 467.0),
+            nades_spacing: 10.0,
+        }
+    }
+}
+
+impl HudLayout {
+    pub fn load_data(&mut self, cfg: &Ini) {
+        let data = match cfg.section(Some("INTERFACE".to_owned())) {
+            Some(data) => data,
+            None => return,
+        };
+
+        let read = |key: &str, value: &mut f32| {
+            if let Some(v) = data.get(key).and_then(|v| f32::from_str(v.trim()).ok()) {
+                *value = v;
+            }
+        };
+
+        let read_point = |name: &str, point: &mut Vec2| {
+            read(&format!("{}_X", name), &mut point.x);
+            read(&format!("{}_Y", name), &mut point.y);
+        };
+
+        let read_bar = |name: &str, bar: &mut Bar| {
+            read_point(name, &mut bar.pos);
+            read(&format!("{}_Width", name), &mut bar.size.x);
+            read(&format!("{}_Height", name), &mut bar.size.y);
+        };
+
+        let mut alpha = f32::from(self.alpha);
+        read("Alpha", &mut alpha);
+        self.alpha = alpha.clamp(0.0, 255.0) as u8;
+
+        read_point("HealthIco", &mut self.health_icon);
+        read_bar("HealthBar", &mut self.health_bar);
+        read_bar("VestBar", &mut self.vest_bar);
+        read_point("AmmoIco", &mut self.ammo_icon);
+        read_bar("ReloadBar", &mut self.reload_bar);
+        read_point("AmmoCount", &mut self.ammo_count);
+        read_point("Weapon", &mut self.weapon);
+        read_point("WeaponName", &mut self.weapon_name);
+        read_point("JetIco", &mut self.jet_icon);
+        read_bar("JetBar", &mut self.jet_bar);
+        read_point("Nades", &mut self.nades);
+        read("Nades_Width", &mut self.nades_spacing);
+    }
+}
+
+pub fn weapon_icon(kind: WeaponKind) -> Option<gfx::Interface> {
+    match kind {
+        WeaponKind::Knife | WeaponKind::ThrownKnife => Some(gfx::Interface::GunsKnife),
+        WeaponKind::Chainsaw => Some(gfx::Interface::GunsChainsaw),
+        WeaponKind::LAW => Some(gfx::Interface::GunsLaw),
+        WeaponKind::Flamer => Some(gfx::Interface::GunsFlamer),
+        WeaponKind::Bow | WeaponKind::FlameBow => Some(gfx::Interface::GunsBow),
+        WeaponKind::NoWeapon => Some(gfx::Interface::GunsFist),
+        WeaponKind::M2 => Some(gfx::Interface::GunsM2),
+        WeaponKind::FragGrenade => Some(gfx::Interface::Nade),
+        WeaponKind::ClusterGrenade | WeaponKind::Cluster => Some(gfx::Interface::ClusterNade),
+        kind if kind.index() <= WeaponKind::USSOCOM.index() => {
+            Some(gfx::Interface::GunsDeagles + kind.index())
+        }
+        _ => None,
+    }
+}
+
+fn render_bar(batch: &mut DrawBatch, sprite: &Sprite, bar: Bar, fraction: f32, color: Color) {
+    let fraction = fraction.clamp(0.0, 1.0);
+
+    if fraction > 0.0 {
+        batch.add_sprite(
+            sprite,
+            color,
+            Transform::FromOrigin {
+                pos: bar.pos,
+                scale: vec2(
+                    fraction * bar.size.x / sprite.width,
+                    bar.size.y / sprite.height,
+                ),
+                rot: (rad(0.0), Vec2::zero()),
+            },
+        );
+    }
+}
+
+// Health, vest, ammo and reload, jet fuel, grenades and the weapon of the local player,
+// in screen space.
+pub fn render_hud(
+    soldier: &Soldier,
+    state: &MainState,
+    layout: &HudLayout,
+    sprites: &[Vec<Sprite>],
+    font: &Font,
+    batch: &mut DrawBatch,
+) {
+    let offset = vec2(0.5 * (state.game_width - SCREEN_WIDTH), 0.0);
+    let color = rgba(255, 255, 255, layout.alpha);
+    let sprite = |s: gfx::Interface| &sprites[s.group().id()][s.id()];
+    let at = |bar: Bar| Bar {
+        pos: bar.pos + offset,
+        size: bar.size,
+    };
+
+    let icon = |batch: &mut DrawBatch, s: gfx::Interface, pos: Vec2| {
+        batch.add_sprite(sprite(s), color, Transform::Pos(pos + offset));
+    };
+
+    // health and vest
+
+    icon(batch, gfx::Interface::Health, layout.health_icon);
+
+    render_bar(
+        batch,
+        sprite(gfx::Interface::HealthBar),
+        at(layout.health_bar),
+        soldier.health / Soldier::max_health(state.realistic_mode),
+        color,
+    );
+
+    render_bar(
+        batch,
+        sprite(gfx::Interface::VestBar),
+        at(layout.vest_bar),
+        soldier.vest / MAX_VEST,
+        color,
+    );
+
+    // ammo, the bar fills back up while reloading
+
+    let weapon = soldier.primary_weapon();
+
+    icon(batch, gfx::Interface::Ammo, layout.ammo_icon);
+
+    let ammo = if weapon.ammo_count == 0 && weapon.reload_time > 0 {
+        1.0 - f32::from(weapon.reload_time_count) / f32::from(weapon.reload_time)
+    } else if weapon.ammo > 0 {
+        f32::from(weapon.ammo_count) / f32::from(weapon.ammo)
+    } else {
+        0.0
+    };
+
+    render_bar(
+        batch,
+        sprite(gfx::Interface::ReloadBar),
+        at(layout.reload_bar),
+        ammo,
+        color,
+    );
+
+    if let Some(s) = weapon_icon(weapon.kind) {
+        icon(batch, s, layout.weapon);
+    }
+
+    let text = TextStyle::new(color).shadow(rgba(0, 0, 0, layout.alpha));
+
+    batch.add_text(
+        font,
+        &weapon.ammo_count.to_string(),
+        layout.ammo_count + offset,
+        &text,
+    );
+
+    batch.add_text(
+        font,
+        weapon.name,
+        layout.weapon_name + offset,
+        &text.align(Align::Right),
+    );
+
+    // jet fuel
+
+    icon(batch, gfx::Interface::Jet, layout.jet_icon);
+
+    if state.map.start_jet > 0 {
+        render_bar(
+            batch,
+            sprite(gfx::Interface::JetBar),
+            at(layout.jet_bar),
+            soldier.jets_count as f32 / state.map.start_jet as f32,
+            color,
+        );
+    }
+
+    // grenades
+
+    let grenades = soldier.tertiary_weapon();
+    let nade = iif!(
+        grenades.kind == WeaponKind::ClusterGrenade,
+        gfx::Interface::ClusterNade,
+        gfx::Interface::Nade
+    );
+
+    for i in 0..grenades.ammo_count {
+        let pos = layout.nades + vec2(f32::from(i) * layout.nades_spacing, 0.0);
+        icon(batch, nade, pos);
+    }
+}
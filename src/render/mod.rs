@@ -1,16 +1,32 @@
 use super::*;
 
 pub mod bullets;
+pub mod chat;
+pub mod debug;
+pub mod flags;
 pub mod game;
 pub mod gfx;
+pub mod hud;
 pub mod map;
+pub mod scoreboard;
 pub mod soldiers;
+pub mod stationary;
+pub mod things;
 
+pub use self::debug::{BulletTraces, DebugOptions};
 pub use self::game::GameGraphics;
+pub use self::scoreboard::KillFeed;
 
 use self::bullets::*;
+use self::chat::*;
+use self::debug::*;
+use self::flags::*;
+use self::hud::*;
 use self::map::*;
+use self::scoreboard::*;
 use self::soldiers::*;
+use self::stationary::*;
+use self::things::*;
 use gfx2d::*;
 
 fn filename_override(prefix: &str, fname: &str) -> ::std::path::PathBuf {
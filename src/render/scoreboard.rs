@@ -0,0 +1,222 @@
+use super::*;
+use gfx::SpriteData;
+
+const SECOND: i32 = 60;
+const FEED_TIME: i32 = SECOND * 10;
+const FEED_FADE: i32 = SECOND;
+const FEED_LINES: usize = 5;
+const FEED_ROW: f32 = 16.0;
+const SCORES_WIDTH: f32 = 420.0;
+const SCORES_ROW: f32 = 14.0;
+
+#[derive(Debug, Copy, Clone)]
+struct Kill {
+    killer: usize,
+    victim: usize,
+    weapon: WeaponKind,
+    time: i32,
+}
+
+// The last few kills, shown in the top right corner until they time out.
+#[derive(Debug, Default)]
+pub struct KillFeed {
+    kills: Vec<Kill>,
+}
+
+impl KillFeed {
+    pub fn add(&mut self, kill: &KillParams) {
+        self.kills.push(Kill {
+            killer: kill.killer,
+            victim: kill.victim,
+            weapon: kill.weapon,
+            time: FEED_TIME,
+        });
+
+        if self.kills.len() > FEED_LINES {
+            self.kills.remove(0);
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.kills.iter_mut().for_each(|k| k.time -= 1);
+        self.kills.retain(|k| k.time > 0);
+    }
+}
+
+fn team_color(team: Team) -> Color {
+    team.color().unwrap_or_else(|| rgb(255, 255, 255))
+}
+
+fn with_alpha(mut color: Color, alpha: u8) -> Color {
+    color.set_a(alpha);
+    color
+}
+
+fn rect(batch: &mut DrawBatch, pos: Vec2, size: Vec2, color: Color) {
+    batch.add_quad(
+        None,
+        &[
+            vertex(pos, Vec2::zero(), color),
+            vertex(pos + vec2(size.x, 0.0), Vec2::zero(), color),
+            vertex(pos + size, Vec2::zero(), color),
+            vertex(pos + vec2(0.0, size.y), Vec2::zero(), color),
+        ],
+    );
+}
+
+// Killer, weapon and victim, newest at the bottom, right aligned to the screen edge.
+// Suicides only show the victim.
+pub fn render_kill_feed(
+    state: &MainState,
+    soldiers: &[Soldier],
+    sprites: &[Vec<Sprite>],
+    font: &Font,
+    batch: &mut DrawBatch,
+) {
+    let right = state.game_width - 8.0;
+    let mut y = 8.0;
+
+    for kill in &state.kill_feed.kills {
+        let find = |num: usize| soldiers.iter().find(|s| s.num == num);
+        let alpha = (255 * kill.time.min(FEED_FADE) / FEED_FADE) as u8;
+
+        let victim = match find(kill.victim) {
+            Some(victim) => victim,
+            None => continue,
+        };
+
+        let style = |team: Team| {
+            TextStyle::new(with_alpha(team_color(team), alpha))
+                .align(Align::Right)
+                .shadow(rgba(0, 0, 0, alpha))
+        };
+
+        let text_y = y + 0.5 * (FEED_ROW - font.line_height());
+        let name = &victim.player.name;
+        let mut x = right;
+
+        batch.add_text(font, name, vec2(x, text_y), &style(victim.team));
+        x -= font.measure(name).x + 4.0;
+
+        if let Some(killer) = find(kill.killer).filter(|_| kill.killer != kill.victim) {
+            if let Some(icon) = weapon_icon(kill.weapon) {
+                let sprite = &sprites[icon.group().id()][icon.id()];
+                let pos = vec2(x - sprite.width, y + 0.5 * (FEED_ROW - sprite.height));
+
+                batch.add_sprite(sprite, rgba(255, 255, 255, alpha), Transform::Pos(pos));
+                x -= sprite.width + 4.0;
+            }
+
+            let name = &killer.player.name;
+            batch.add_text(font, name, vec2(x, text_y), &style(killer.team));
+        }
+
+        y += FEED_ROW;
+    }
+}
+
+// Held-down score table, one block per team in team games and a single block
+// otherwise. Players are sorted by score and flag carriers get the flag icon.
+pub fn render_scoreboard(
+    state: &MainState,
+    soldiers: &[Soldier],
+    sprites: &[Vec<Sprite>],
+    font: &Font,
+    batch: &mut DrawBatch,
+) {
+    let teams: &[Team] = match state.round.mode.teams() {
+        0 => &[Team::None],
+        n => &Team::values()[..n],
+    };
+
+    // a title row, a gap and a team row per team and a row per player
+    let team_rows = iif!(teams[0] == Team::None, 0.5, 1.5);
+    let players = soldiers.iter().filter(|s| s.active).count();
+    let rows = 1.0 + teams.len() as f32 * team_rows + players as f32;
+    let size = vec2(SCORES_WIDTH, rows * SCORES_ROW + 16.0);
+    let pos = vec2(0.5 * (state.game_width - size.x), 60.0);
+    let columns = [
+        ("Kills", 220.0),
+        ("Deaths", 270.0),
+        ("Caps", 320.0),
+        ("Ping", 370.0),
+    ];
+    let text_dy = 0.5 * (SCORES_ROW - font.line_height());
+    let flag = gfx::Interface::Flag;
+    let flag = &sprites[flag.group().id()][flag.id()];
+
+    rect(batch, pos, size, rgba(0, 0, 0, 160));
+
+    let mut y = pos.y + 8.0;
+    let header = TextStyle::new(rgb(160, 160, 160));
+
+    batch.add_text(font, "Name", vec2(pos.x + 8.0, y + text_dy), &header);
+
+    for &(title, x) in &columns {
+        let style = header.align(Align::Right);
+        batch.add_text(font, title, vec2(pos.x + x, y + text_dy), &style);
+    }
+
+    y += SCORES_ROW;
+
+    for &team in teams {
+        let color = team_color(team);
+        let style = TextStyle::new(color).shadow(rgb(0, 0, 0));
+        let mut players: Vec<&Soldier> = soldiers
+            .iter()
+            .filter(|s| s.active && s.team == team)
+            .collect();
+
+        players.sort_by_key(|s| (-s.score, -(s.kills as i32), s.deaths));
+
+        y += 0.5 * SCORES_ROW;
+
+        if team != Team::None {
+            let title = format!("{:?}", team);
+            let score = state.round.team_scores[team.index()].to_string();
+
+            rect(
+                batch,
+                vec2(pos.x + 4.0, y),
+                vec2(size.x - 8.0, SCORES_ROW),
+                with_alpha(color, 60),
+            );
+
+            batch.add_text(font, &title, vec2(pos.x + 8.0, y + text_dy), &style);
+            batch.add_text(
+                font,
+                &score,
+                vec2(pos.x + size.x - 8.0, y + text_dy),
+                &style.align(Align::Right),
+            );
+
+            y += SCORES_ROW;
+        }
+
+        for soldier in players {
+            let text_y = y + text_dy;
+            // every player is local until there's networking, so nobody has a ping
+            let values = [soldier.kills, soldier.deaths, soldier.caps, 0];
+
+            batch.add_text(
+                font,
+                &soldier.player.name,
+                vec2(pos.x + 8.0, text_y),
+                &style,
+            );
+
+            for (&(_, x), value) in columns.iter().zip(values.iter()) {
+                let style = style.align(Align::Right);
+                batch.add_text(font, &value.to_string(), vec2(pos.x + x, text_y), &style);
+            }
+
+            if let Some(held) = state.flags.iter().find(|f| f.holder == Some(soldier.num)) {
+                let color = held.team.color().unwrap_or_else(|| rgb(255, 255, 0));
+                let pos = vec2(pos.x + 384.0, y + 0.5 * (SCORES_ROW - flag.height));
+                batch.add_sprite(flag, color, Transform::Pos(pos));
+            }
+
+            y += SCORES_ROW;
+        }
+    }
+}
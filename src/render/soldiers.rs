@@ -5,6 +5,8 @@ use ini::Ini;
 use std::str::FromStr;
 use typenum::U256;
 
+const BURNING_POINTS: [usize; 4] = [3, 4, 10, 12];
+
 type BitSet = BitArray<u64, U256>;
 
 #[derive(Debug, Copy, Clone)]
@@ -113,9 +115,10 @@ pub fn render_soldier(
     sprites: &[Vec<Sprite>],
     batch: &mut DrawBatch,
     frame_percent: f32,
+    realistic_mode: bool,
 ) {
     let sk = &soldier.skeleton;
-    let (colors, alpha) = colors_and_alpha(soldier);
+    let (colors, alpha) = colors_and_alpha(soldier, realistic_mode);
     let has_blood = alpha[SoldierAlpha::Blood as usize] > 0;
     let visible = parts_visibility(&soldier_graphics.base_visibility, soldier, has_blood);
 
@@ -176,17 +179,40 @@ pub fn render_soldier(
             );
         }
     }
+
+    if soldier.on_fire > 0 {
+        render_burning(soldier, sprites, batch, frame_percent);
+    }
+}
+
+fn render_burning(
+    soldier: &Soldier,
+    sprites: &[Vec<Sprite>],
+    batch: &mut DrawBatch,
+    frame_percent: f32,
+) {
+    let sk = &soldier.skeleton;
+
+    for point in BURNING_POINTS.iter() {
+        let pos = lerp(sk.old_pos(*point), sk.pos(*point), frame_percent);
+        let flicker = vec2(
+            rand::random::<f32>() * 4.0 - 2.0,
+            rand::random::<f32>() * 4.0 - 2.0,
+        );
+
+        render_flame(pos + flicker, soldier.alpha, sprites, batch);
+    }
 }
 
-fn colors_and_alpha(soldier: &Soldier) -> ([Color; 7], [u8; 3]) {
+fn colors_and_alpha(soldier: &Soldier, realistic_mode: bool) -> ([Color; 7], [u8; 3]) {
     let mut alpha_base = soldier.alpha;
     let mut alpha_blood = f32::max(0.0, f32::min(255.0, 200.0 - soldier.health.round())) as u8;
     let mut color_cygar = rgb(255, 255, 255);
     let color_none = rgb(255, 255, 255);
-    let color_main = rgb(0, 0, 0); // TODO: Player.Color1
-    let color_pants = rgb(0, 0, 0); // TODO: Player.Color2
-    let color_skin = rgb(230, 180, 120); // TODO: Player.SkinColor
-    let color_hair = rgb(0, 0, 0); // TODO: Player.HairColor
+    let color_main = soldier.player.shirt_color(soldier.team);
+    let color_pants = soldier.player.pants_color;
+    let color_skin = soldier.player.skin_color;
+    let color_hair = soldier.player.hair_color;
     let color_headblood = rgb(172, 169, 168);
     let alpha_nades: u8;
 
@@ -194,8 +220,6 @@ fn colors_and_alpha(soldier: &Soldier) -> ([Color; 7], [u8; 3]) {
         color_cygar = rgb(97, 97, 97);
     }
 
-    let realistic_mode = false; // TODO: use real value
-
     if soldier.health > (90.0 - 40.0 * f32::from(realistic_mode as u8)) {
         alpha_blood = 0;
     }
@@ -269,9 +293,8 @@ fn parts_visibility(base_visibility: &BitSet, soldier: &Soldier, blood: bool) ->
         visible.set(index + i as usize, true);
     }
 
-    let chain = 0; // TODO: Player.Chain (this seems broken, check skeleton)
-
-    match chain {
+    // TODO: chains seem broken, check skeleton
+    match soldier.player.chain {
         1 => {
             visible.set(SoldierPart::SilverLchain.id(), true);
             visible.set(SoldierPart::SilverRchain.id(), true);
@@ -307,20 +330,19 @@ fn parts_visibility(base_visibility: &BitSet, soldier: &Soldier, blood: bool) ->
         };
 
         if soldier.wear_helmet == 1 {
-            let head_cap = gfx::Soldier::Helm; // TODO: Player.HeadCap
-
-            match head_cap {
-                gfx::Soldier::Helm if grabbed => visible.set(SoldierPart::GrabbedHelmet.id(), true),
-                gfx::Soldier::Kap if grabbed => visible.set(SoldierPart::GrabbedHat.id(), true),
-                gfx::Soldier::Helm if !grabbed => visible.set(SoldierPart::Helmet.id(), true),
-                gfx::Soldier::Kap if !grabbed => visible.set(SoldierPart::Hat.id(), true),
-                _ => {}
+            match soldier.player.head_cap {
+                HeadCap::Helmet if grabbed => visible.set(SoldierPart::GrabbedHelmet.id(), true),
+                HeadCap::Hat if grabbed => visible.set(SoldierPart::GrabbedHat.id(), true),
+                HeadCap::Helmet => visible.set(SoldierPart::Helmet.id(), true),
+                HeadCap::Hat => visible.set(SoldierPart::Hat.id(), true),
+                HeadCap::None => {}
             }
         }
 
-        let hair_style = 3; // TODO: Player.HairStyle
+        let hair_style = soldier.player.hair_style;
+        let bare_head = soldier.wear_helmet != 1 || soldier.player.head_cap == HeadCap::None;
 
-        if grabbed || soldier.wear_helmet != 1 || hair_style == 3 {
+        if grabbed || bare_head || hair_style == 3 {
             match hair_style {
                 1 => for i in 0..6 {
                     visible.set(SoldierPart::HairDreadlocks.id() + i, true);
@@ -333,6 +355,19 @@ fn parts_visibility(base_visibility: &BitSet, soldier: &Soldier, blood: bool) ->
         }
     }
 
+    // parts spanning a torn constraint are hidden, the rest follow their own particles
+
+    if soldier.dead_meat {
+        for (i, part) in SoldierPart::data().iter().enumerate() {
+            if soldier
+                .skeleton
+                .constraint_broken(part.point.0, part.point.1)
+            {
+                visible.set(i, false);
+            }
+        }
+    }
+
     // secondary weapon (on the back)
 
     let index = soldier.secondary_weapon().kind.index();
@@ -0,0 +1,34 @@
+use super::*;
+use gfx::SpriteData;
+
+pub fn render_stationary_gun(
+    gun: &StationaryGun,
+    sprites: &[Vec<Sprite>],
+    batch: &mut DrawBatch,
+    frame_percent: f32,
+) {
+    let angle = lerp(gun.angle_prev, gun.angle, frame_percent);
+
+    let stand = &sprites[gfx::Weapon::M2Stat.group().id()][gfx::Weapon::M2Stat.id()];
+
+    batch.add_sprite(
+        stand,
+        rgb(255, 255, 255),
+        Transform::Pos(gun.pos - vec2(0.5 * stand.width, 0.0)),
+    );
+
+    let rot = iif!(gun.direction == -1, PI - angle, angle);
+    let sprite = &sprites[gfx::Weapon::M2.group().id()][gfx::Weapon::M2.id()];
+    let heat = (255.0 * (1.0 - 0.5 * gun.heat.min(1.0))) as u8;
+
+    batch.add_sprite(
+        sprite,
+        rgb(255, heat, heat),
+        Transform::WithPivot {
+            pivot: vec2(0.3 * sprite.width, 0.5 * sprite.height),
+            pos: gun.pos,
+            scale: vec2(1.0, iif!(gun.direction == -1, -1.0, 1.0)),
+            rot: rad(rot),
+        },
+    );
+}
@@ -0,0 +1,66 @@
+use super::*;
+use gfx::SpriteData;
+
+pub fn render_thing(
+    thing: &Thing,
+    sprites: &[Vec<Sprite>],
+    batch: &mut DrawBatch,
+    frame_percent: f32,
+) {
+    let frame_percent = iif!(thing.active, frame_percent, 1.0);
+    let pos = lerp(thing.particle.old_pos, thing.particle.pos, frame_percent);
+    let rot = lerp(thing.rotation_prev, thing.rotation, frame_percent);
+
+    match thing.kind {
+        ThingKind::Weapon(weapon) => {
+            if let Some(sprite) = weapon.sprite {
+                let sprite = &sprites[sprite.group().id()][sprite.id()];
+
+                // keep the weapon upright when it points to the left
+                let scale = iif!(rot.cos() < 0.0, vec2(1.0, -1.0), vec2(1.0, 1.0));
+
+                batch.add_sprite(
+                    sprite,
+                    rgb(255, 255, 255),
+                    Transform::WithPivot {
+                        pivot: vec2(0.5 * sprite.width, 0.5 * sprite.height),
+                        pos,
+                        scale,
+                        rot: rad(rot),
+                    },
+                );
+            }
+        }
+        ThingKind::HeadCap(cap, color) => {
+            if let Some(sprite) = cap.sprite() {
+                let sprite = &sprites[sprite.group().id()][sprite.id()];
+
+                batch.add_sprite(
+                    sprite,
+                    color,
+                    Transform::WithPivot {
+                        pivot: vec2(0.5 * sprite.width, 0.5 * sprite.height),
+                        pos,
+                        scale: vec2(1.0, 1.0),
+                        rot: rad(rot),
+                    },
+                );
+            }
+        }
+        ThingKind::Kit(kit) => {
+            let sprite = kit.sprite();
+            let sprite = &sprites[sprite.group().id()][sprite.id()];
+
+            batch.add_sprite(
+                sprite,
+                rgb(255, 255, 255),
+                Transform::WithPivot {
+                    pivot: vec2(0.5 * sprite.width, 0.5 * sprite.height),
+                    pos,
+                    scale: vec2(1.0, 1.0),
+                    rot: rad(rot),
+                },
+            );
+        }
+    }
+}
@@ -1,9 +1,8 @@
 use super::*;
-use glutin;
 
 const SLIDELIMIT: f32 = 0.2;
 const GRAV: f32 = 0.06;
-const SURFACECOEFX: f32 = 0.970;
+pub const SURFACECOEFX: f32 = 0.970;
 const SURFACECOEFY: f32 = 0.970;
 const CROUCHMOVESURFACECOEFX: f32 = 0.85;
 const CROUCHMOVESURFACECOEFY: f32 = 0.97;
@@ -17,17 +16,48 @@ const POS_PRONE: u8 = 3;
 const MAX_VELOCITY: f32 = 11.0;
 const SOLDIER_COL_RADIUS: f32 = 3.0;
 
+const RESPAWN_TIME: i32 = 60 * 3;
+
+const DEFAULT_HEALTH: f32 = 150.0;
+const REALISTIC_HEALTH: f32 = 65.0;
+const REGENERATE_HEALTH: f32 = 0.5;
+const HURT_FLAGGERS_DAMAGE: f32 = 1.0;
+
+const BARRETT_STAND_SPREAD: f32 = 0.15;
+
+pub const MAX_VEST: f32 = 100.0;
+const VEST_ABSORB: f32 = 0.75;
+const BERSERKER_DAMAGE: f32 = 4.0;
+const PREDATOR_ALPHA: u8 = 20;
+
+const HALF_DEAD_HEALTH: f32 = 25.0;
+const GIB_IMPACT: f32 = 5.0;
+const GIB_STRESS: f32 = 0.5;
+const EXPLOSION_DAMAGE: f32 = 0.1;
+const BURN_TIME: u8 = 60 * 4;
+const BURN_DAMAGE: f32 = 0.25;
+const EXPLOSION_IMPACT: f32 = 0.005;
+const LEG_PARTICLES: [usize; 8] = [1, 2, 3, 4, 5, 6, 17, 18];
+
 lazy_static! {
     static ref SOLDIER_SKELETON: ParticleSystem =
         ParticleSystem::load_from_file("gostek.po", 4.5, 1.0, 1.06 * GRAV, 0.0, 0.9945);
 }
 
+#[derive(Debug, Copy, Clone)]
+pub struct KillParams {
+    pub killer: usize,
+    pub victim: usize,
+    pub weapon: WeaponKind,
+}
+
 #[allow(dead_code)]
 pub struct Soldier {
     pub active: bool,
     pub dead_meat: bool,
     pub style: u8,
     pub num: usize,
+    pub team: Team,
     pub visible: u8,
     pub on_ground: bool,
     pub on_ground_for_law: bool,
@@ -46,14 +76,28 @@ pub struct Soldier {
     pub idle_random: i8,
     pub position: u8,
     pub on_fire: u8,
+    pub burned_by: Option<(usize, WeaponKind)>,
     pub collider_distance: u8,
     pub half_dead: bool,
+    pub respawn_counter: i32,
+    pub last_hit: Option<(Vec2, Vec2)>,
+    pub last_attacker: Option<(usize, WeaponKind)>,
+    pub kills: u32,
+    pub deaths: u32,
+    pub caps: u32,
+    pub score: i32,
+    pub stat_gun: Option<usize>,
+    pub bonus: Option<Bonus>,
+    pub bonus_time: i32,
+    pub has_flag: bool,
+    pub player: Player,
     pub skeleton: ParticleSystem,
     pub legs_animation: AnimState,
     pub body_animation: AnimState,
     pub control: Control,
     pub active_weapon: usize,
     pub weapons: [Weapon; 3],
+    pub loadout: Loadout,
     pub fired: u8,
     pub particle: Particle,
 }
@@ -75,6 +119,16 @@ impl Soldier {
         &self.weapons[2]
     }
 
+    // Rambomatch rule: whoever carries the bow is stuck with it.
+    pub fn holds_bow(&self) -> bool {
+        self.primary_weapon()
+            .is_any(&[WeaponKind::Bow, WeaponKind::FlameBow])
+    }
+
+    pub fn crouched_or_prone(&self) -> bool {
+        self.position == POS_CROUCH || self.position == POS_PRONE
+    }
+
     pub fn switch_weapon(&mut self) {
         let w = (self.active_weapon + 1) % 2;
         self.active_weapon = w;
@@ -83,46 +137,307 @@ impl Soldier {
         // burst_count = 0;
     }
 
-    pub fn update_keys(&mut self, input: &glutin::KeyboardInput) {
-        match input.state {
-            glutin::ElementState::Pressed => match input.virtual_keycode {
-                Some(glutin::VirtualKeyCode::A) => self.control.left = true,
-                Some(glutin::VirtualKeyCode::D) => self.control.right = true,
-                Some(glutin::VirtualKeyCode::W) => self.control.up = true,
-                Some(glutin::VirtualKeyCode::S) => self.control.down = true,
-                Some(glutin::VirtualKeyCode::Q) => self.control.change = true,
-                Some(glutin::VirtualKeyCode::E) => self.control.throw = true,
-                Some(glutin::VirtualKeyCode::F) => self.control.drop = true,
-                Some(glutin::VirtualKeyCode::X) => self.control.prone = true,
-                _ => {}
-            },
-            glutin::ElementState::Released => match input.virtual_keycode {
-                Some(glutin::VirtualKeyCode::A) => self.control.left = false,
-                Some(glutin::VirtualKeyCode::D) => self.control.right = false,
-                Some(glutin::VirtualKeyCode::W) => self.control.up = false,
-                Some(glutin::VirtualKeyCode::S) => self.control.down = false,
-                Some(glutin::VirtualKeyCode::Q) => self.control.change = false,
-                Some(glutin::VirtualKeyCode::E) => self.control.throw = false,
-                Some(glutin::VirtualKeyCode::F) => self.control.drop = false,
-                Some(glutin::VirtualKeyCode::X) => self.control.prone = false,
-                _ => {}
-            },
-        }
-    }
-
-    pub fn update_mouse_button(&mut self, input: &(glutin::ElementState, glutin::MouseButton)) {
-        let pressed = match input.0 {
-            glutin::ElementState::Pressed => true,
-            glutin::ElementState::Released => false,
+    pub fn pick_up_weapon(&mut self, weapon: Weapon, realistic: bool) -> bool {
+        if self.holds_bow() {
+            return false;
+        }
+
+        // whoever takes the bow becomes the rambo and gives up their guns for it
+        if weapon.is_any(&[WeaponKind::Bow, WeaponKind::FlameBow]) {
+            self.weapons[0] = Weapon::new(WeaponKind::Bow, realistic);
+            self.weapons[1] = Weapon::new(WeaponKind::FlameBow, realistic);
+            self.active_weapon = 0;
+            return true;
+        }
+
+        let slot = if self.primary_weapon().kind == WeaponKind::NoWeapon {
+            self.active_weapon
+        } else if weapon.kind.group() == WeaponGroup::Secondary
+            && self.secondary_weapon().kind == WeaponKind::NoWeapon
+        {
+            (self.active_weapon + 1) % 2
+        } else {
+            return false;
         };
-        match input.1 {
-            glutin::MouseButton::Left => self.control.fire = pressed,
-            glutin::MouseButton::Right => self.control.jets = pressed,
-            _ => (),
+
+        self.weapons[slot] = weapon;
+        self.weapons[slot].start_up_time_count = weapon.start_up_time;
+        true
+    }
+
+    pub fn pick_up_kit(&mut self, kit: KitKind, realistic: bool) -> bool {
+        match kit {
+            KitKind::Medikit => {
+                if self.health >= Soldier::max_health(realistic) {
+                    return false;
+                }
+
+                self.health = Soldier::max_health(realistic);
+            }
+            KitKind::GrenadeKit | KitKind::ClusterKit => {
+                let kind = iif!(
+                    kit == KitKind::GrenadeKit,
+                    WeaponKind::FragGrenade,
+                    WeaponKind::ClusterGrenade
+                );
+
+                let grenades = self.tertiary_weapon();
+
+                if grenades.kind == kind && grenades.ammo_count == grenades.ammo {
+                    return false;
+                }
+
+                self.weapons[2] = Weapon::new(kind, realistic);
+            }
+            KitKind::Vest => {
+                if self.vest >= MAX_VEST {
+                    return false;
+                }
+
+                self.vest = MAX_VEST;
+            }
+            KitKind::Berserker | KitKind::Predator | KitKind::Flamegod => {
+                if self.bonus.is_some() {
+                    return false;
+                }
+
+                let bonus = match kit {
+                    KitKind::Berserker => Bonus::Berserker,
+                    KitKind::Predator => Bonus::Predator,
+                    _ => Bonus::Flamegod,
+                };
+
+                if bonus == Bonus::Flamegod {
+                    self.weapons[self.active_weapon] = Weapon::new(WeaponKind::Flamer, realistic);
+                }
+
+                self.bonus = Some(bonus);
+                self.bonus_time = bonus.duration();
+            }
+        }
+
+        true
+    }
+
+    // The flamer only lasts as long as the flamegod bonus, after that the soldier
+    // gets back the weapon from their loadout.
+    pub fn end_bonus(&mut self, realistic: bool) {
+        if self.bonus == Some(Bonus::Flamegod) && self.primary_weapon().kind == WeaponKind::Flamer {
+            let kind = iif!(
+                self.active_weapon == 0,
+                self.loadout.primary,
+                self.loadout.secondary
+            );
+
+            self.weapons[self.active_weapon] = Weapon::new(kind, realistic);
+        }
+
+        self.bonus = None;
+        self.bonus_time = 0;
+    }
+
+    pub fn max_health(realistic: bool) -> f32 {
+        iif!(realistic, REALISTIC_HEALTH, DEFAULT_HEALTH)
+    }
+
+    pub fn drop_weapon(&mut self, emitter: &mut Vec<EmitterItem>, velocity: Vec2, realistic: bool) {
+        let weapon = *self.primary_weapon();
+
+        if weapon.kind == WeaponKind::NoWeapon {
+            return;
+        }
+
+        emitter.push(EmitterItem::Thing(ThingParams {
+            kind: ThingKind::Weapon(weapon),
+            position: self.skeleton.pos(16),
+            velocity,
+            rotation: iif!(self.direction == 1, 0.0, PI),
+            owner: self.num,
+            stuck: false,
+        }));
+
+        self.weapons[self.active_weapon] = Weapon::new(WeaponKind::NoWeapon, realistic);
+    }
+
+    // A headshot sends the helmet or hat flying off the head, the soldier stays
+    // bare-headed until respawning.
+    pub fn knock_off_helmet(&mut self, velocity: Vec2, emitter: &mut Vec<EmitterItem>) {
+        if self.wear_helmet != 1 || self.player.head_cap == HeadCap::None {
+            return;
+        }
+
+        self.wear_helmet = 0;
+
+        emitter.push(EmitterItem::Thing(ThingParams {
+            kind: ThingKind::HeadCap(self.player.head_cap, self.player.shirt_color(self.team)),
+            position: self.skeleton.pos(12),
+            velocity: self.particle.velocity + velocity + vec2(0.0, -1.0),
+            rotation: iif!(self.direction == 1, 0.0, PI),
+            owner: self.num,
+            stuck: false,
+        }));
+    }
+
+    // Team mates are the other soldiers on the same side, nobody is on a side in
+    // free for all games.
+    pub fn is_teammate(&self, team: Team, num: usize) -> bool {
+        team != Team::None && team == self.team && num != self.num
+    }
+
+    pub fn body_center(&self) -> Vec2 {
+        iif!(
+            self.dead_meat,
+            self.particle.pos,
+            self.particle.pos - vec2(0.0, 10.0)
+        )
+    }
+
+    pub fn hit(
+        &mut self,
+        damage: f32,
+        pos: Vec2,
+        impact: Vec2,
+        attacker: usize,
+        weapon: WeaponKind,
+    ) {
+        if self.dead_meat {
+            self.push_skeleton(pos, impact);
+        } else if self.bonus != Some(Bonus::Flamegod) {
+            let absorbed = f32::min(self.vest, damage * VEST_ABSORB);
+            self.vest -= absorbed;
+            self.health -= damage - absorbed;
+            self.last_hit = Some((pos, impact));
+            self.last_attacker = Some((attacker, weapon));
+        }
+    }
+
+    // The burn keeps hurting after the flame is gone, so whoever lit the soldier up
+    // is remembered and credited if the fire finishes them off. Fires started by the
+    // map have nobody to credit.
+    pub fn ignite(&mut self, burned_by: Option<(usize, WeaponKind)>) {
+        if !self.dead_meat && self.bonus != Some(Bonus::Flamegod) {
+            self.on_fire = BURN_TIME;
+            self.burned_by = burned_by;
         }
     }
 
-    pub fn new(spawn: &MapSpawnpoint) -> Soldier {
+    fn update_burning(&mut self, put_out: bool) {
+        if self.on_fire == 0 {
+            return;
+        }
+
+        if put_out {
+            self.on_fire = 0;
+        } else {
+            self.on_fire -= 1;
+
+            match self.burned_by {
+                Some((attacker, weapon)) => {
+                    let pos = self.body_center();
+                    self.hit(BURN_DAMAGE, pos, Vec2::zero(), attacker, weapon);
+                }
+                None => self.health -= BURN_DAMAGE,
+            }
+        }
+    }
+
+    pub fn explosion_hit(&mut self, explosion: &ExplosionParams, friendly_fire: bool) {
+        let center = self.body_center();
+        let dist = distance(center, explosion.position);
+
+        if !self.active || dist > explosion.radius {
+            return;
+        }
+
+        if !friendly_fire && self.is_teammate(explosion.team, explosion.owner) {
+            return;
+        }
+
+        let falloff = 1.0 - dist / explosion.radius;
+        let dir = vec2normalize(center - explosion.position);
+        let impact = dir * explosion.power * falloff * EXPLOSION_IMPACT;
+
+        self.hit(
+            explosion.power * falloff * EXPLOSION_DAMAGE,
+            explosion.position,
+            impact,
+            explosion.owner,
+            explosion.weapon,
+        );
+    }
+
+    // Pushes the skeleton away from pos, strongest at the particles closest to it. A hard
+    // enough push makes the constraints breakable so the body comes apart.
+    pub fn push_skeleton(&mut self, pos: Vec2, impact: Vec2) {
+        for i in 1..=self.skeleton.particles().len() {
+            let falloff = 1.0 / (1.0 + distance(self.skeleton.pos(i), pos) / 10.0);
+            *self.skeleton.old_pos_mut(i) -= impact * falloff;
+        }
+
+        if vec2length(impact) > GIB_IMPACT {
+            self.skeleton.set_max_stress(Some(GIB_STRESS));
+        }
+    }
+
+    pub fn die(&mut self, emitter: &mut Vec<EmitterItem>, realistic: bool) {
+        if !self.dead_meat {
+            self.end_bonus(realistic);
+
+            let velocity = self.particle.velocity;
+            self.drop_weapon(emitter, velocity, realistic);
+
+            // the skeleton keeps its last frame of motion, plus a push around the killing hit
+            if let Some((pos, impact)) = self.last_hit.take() {
+                self.push_skeleton(pos, impact);
+            }
+
+            let (killer, weapon) = self
+                .last_attacker
+                .take()
+                .unwrap_or((self.num, WeaponKind::NoWeapon));
+
+            emitter.push(EmitterItem::Kill(KillParams {
+                killer,
+                victim: self.num,
+                weapon,
+            }));
+
+            self.dead_meat = true;
+            self.half_dead = false;
+            self.on_fire = 0;
+            self.health = 0.0;
+            self.respawn_counter = RESPAWN_TIME;
+            self.control.free_controls();
+        }
+    }
+
+    pub fn reset_score(&mut self) {
+        self.kills = 0;
+        self.deaths = 0;
+        self.caps = 0;
+        self.score = 0;
+    }
+
+    pub fn apply_loadout(&mut self, realistic: bool) {
+        self.weapons[0] = Weapon::new(self.loadout.primary, realistic);
+        self.weapons[1] = Weapon::new(self.loadout.secondary, realistic);
+        self.active_weapon = 0;
+    }
+
+    pub fn respawn(&mut self, spawn: &MapSpawnpoint, realistic: bool) {
+        let old = ::std::mem::replace(self, Soldier::new(spawn, realistic));
+        self.num = old.num;
+        self.kills = old.kills;
+        self.deaths = old.deaths;
+        self.caps = old.caps;
+        self.score = old.score;
+        self.team = old.team;
+        self.loadout = old.loadout;
+        self.player = old.player;
+        self.apply_loadout(realistic);
+    }
+
+    pub fn new(spawn: &MapSpawnpoint, realistic: bool) -> Soldier {
         let particle = Particle {
             active: true,
             pos: vec2(spawn.x as f32, spawn.y as f32),
@@ -134,10 +449,12 @@ impl Soldier {
             ..Default::default()
         };
 
+        let loadout = Loadout::default();
+
         let weapons = [
-            Weapon::new(WeaponKind::DesertEagles, false),
-            Weapon::new(WeaponKind::Chainsaw, false),
-            Weapon::new(WeaponKind::FragGrenade, false),
+            Weapon::new(loadout.primary, realistic),
+            Weapon::new(loadout.secondary, realistic),
+            Weapon::new(WeaponKind::FragGrenade, realistic),
         ];
 
         Soldier {
@@ -145,6 +462,7 @@ impl Soldier {
             dead_meat: false,
             style: 0,
             num: 1,
+            team: Team::None,
             visible: 1,
             on_ground: false,
             on_ground_for_law: false,
@@ -152,25 +470,39 @@ impl Soldier {
             on_ground_permanent: false,
             direction: 1,
             old_direction: 1,
-            health: 150.0,
+            health: Soldier::max_health(realistic),
             alpha: 255,
             jets_count: 0,
             jets_count_prev: 0,
-            wear_helmet: 0,
-            has_cigar: 1,
+            wear_helmet: 1,
+            has_cigar: 0,
             vest: 0.0,
             idle_time: 0,
             idle_random: 0,
             position: 0,
             on_fire: 0,
+            burned_by: None,
             collider_distance: 255,
             half_dead: false,
+            respawn_counter: 0,
+            last_hit: None,
+            last_attacker: None,
+            kills: 0,
+            deaths: 0,
+            caps: 0,
+            score: 0,
+            stat_gun: None,
+            bonus: None,
+            bonus_time: 0,
+            has_flag: false,
+            player: Player::default(),
             skeleton: SOLDIER_SKELETON.clone(),
             legs_animation: AnimState::new(Anim::Stand),
             body_animation: AnimState::new(Anim::Stand),
             control: Default::default(),
             active_weapon: 0,
             weapons,
+            loadout,
             fired: 0,
             particle,
         }
@@ -192,11 +524,24 @@ impl Soldier {
         }
     }
 
-    pub fn handle_special_polytypes(&mut self, map: &MapFile, polytype: PolyType, _pos: Vec2) {
+    pub fn handle_special_polytypes(&mut self, state: &MainState, polytype: PolyType, _pos: Vec2) {
         if polytype == PolyType::Deadly || polytype == PolyType::BloodyDeadly
             || polytype == PolyType::Explosive
         {
-            self.particle.pos = vec2(map.spawnpoints[0].x as f32, map.spawnpoints[0].y as f32);
+            self.health = 0.0;
+        }
+
+        if polytype == PolyType::Lava {
+            self.ignite(None);
+        }
+
+        if polytype == PolyType::HurtsFlaggers && self.has_flag {
+            self.health -= HURT_FLAGGERS_DAMAGE;
+        }
+
+        if polytype == PolyType::Regenerates && !state.realistic_mode {
+            let max_health = Soldier::max_health(state.realistic_mode);
+            self.health = f32::min(max_health, self.health + REGENERATE_HEALTH);
         }
     }
 
@@ -205,6 +550,7 @@ impl Soldier {
         let mut body_y = 0.0;
         let mut arm_s;
 
+        self.particle.gravity = state.gravity;
         self.particle.euler();
         self.control(state, emitter);
 
@@ -256,8 +602,15 @@ impl Soldier {
             self.direction = -1;
         }
 
+        if !self.dead_meat {
+            self.half_dead = self.health > 0.0 && self.health < HALF_DEAD_HEALTH;
+        }
+
         for i in 1..21 {
-            if self.skeleton.active(i) && !self.dead_meat {
+            if self.skeleton.active(i)
+                && !self.dead_meat
+                && !(self.half_dead && LEG_PARTICLES.contains(&i))
+            {
                 let mut pos = Vec2::zero();
                 *self.skeleton.old_pos_mut(i) = self.skeleton.pos(i);
 
@@ -344,11 +697,13 @@ impl Soldier {
             *self.skeleton.pos_mut(19) = self.skeleton.pos(16) - vec2(0.0, 4.0) + r_norm;
         }
 
-        for i in 1..21 {
-            if (self.dead_meat || self.half_dead) && (i < 17) && (i != 7) && (i != 8) {
-                let (x, y) = self.particle.pos.into();
-                self.on_ground = self.check_skeleton_map_collision(map, i, x, y);
-                println!("ok");
+        // half dead soldiers crawl with their legs dragged along as a ragdoll
+        if self.half_dead {
+            self.skeleton.do_verlet_timestep_of(&LEG_PARTICLES);
+
+            for i in LEG_PARTICLES.iter() {
+                let (x, y) = self.skeleton.pos(*i).into();
+                self.check_skeleton_map_collision(map, *i, x, y);
             }
         }
 
@@ -359,10 +714,10 @@ impl Soldier {
             self.on_ground = false;
 
             let (x, y) = self.particle.pos.into();
-            self.check_map_collision(map, x - 3.5, y - 12.0, 1);
+            self.check_map_collision(state, x - 3.5, y - 12.0, 1);
 
             let (x, y) = self.particle.pos.into();
-            self.check_map_collision(map, x + 3.5, y - 12.0, 1);
+            self.check_map_collision(state, x + 3.5, y - 12.0, 1);
 
             body_y = 0.0;
             arm_s = 0.0;
@@ -398,18 +753,18 @@ impl Soldier {
             }
 
             let (x, y) = self.particle.pos.into();
-            self.on_ground = self.check_map_collision(map, x + 2.0, y + 2.0 - body_y, 0);
+            self.on_ground = self.check_map_collision(state, x + 2.0, y + 2.0 - body_y, 0);
 
             let (x, y) = self.particle.pos.into();
-            self.on_ground |= self.check_map_collision(map, x - 2.0, y + 2.0 - arm_s, 0);
+            self.on_ground |= self.check_map_collision(state, x - 2.0, y + 2.0 - arm_s, 0);
 
             let (x, y) = self.particle.pos.into();
             let grounded = self.on_ground;
-            self.on_ground_for_law = self.check_radius_map_collision(map, x, y - 1.0, grounded);
+            self.on_ground_for_law = self.check_radius_map_collision(state, x, y - 1.0, grounded);
 
             let (x, y) = self.particle.pos.into();
             let grounded = self.on_ground || self.on_ground_for_law;
-            self.on_ground |= self.check_map_vertices_collision(map, x, y, 3.0, grounded);
+            self.on_ground |= self.check_map_vertices_collision(state, x, y, 3.0, grounded);
 
             if !(self.on_ground ^ self.on_ground_last_frame) {
                 self.on_ground_permanent = self.on_ground;
@@ -417,24 +772,42 @@ impl Soldier {
 
             self.on_ground_last_frame = self.on_ground;
 
+            let rolling = self.body_animation.is_any(&[Anim::Roll, Anim::RollBack]);
+            let in_water = map.in_polytype(self.body_center(), PolyType::Water);
+            self.update_burning(rolling || in_water);
+
             if (self.jets_count < map.start_jet) && !(self.control.jets) {
-                //if self.on_ground
-                /* (MainTickCounter mod 2 = 0) */
-                {
+                // realistic mode only refuels on the ground
+                if self.on_ground || !state.realistic_mode {
                     self.jets_count += 1;
                 }
             }
 
-            self.alpha = 255;
+            if self.bonus.is_some() {
+                self.bonus_time -= 1;
+
+                if self.bonus_time <= 0 {
+                    self.end_bonus(state.realistic_mode);
+                }
+            }
+
+            self.alpha = iif!(self.bonus == Some(Bonus::Predator), PREDATOR_ALPHA, 255);
 
             self.skeleton.do_verlet_timestep_for(22, 29);
             self.skeleton.do_verlet_timestep_for(24, 30);
         }
 
         if self.dead_meat {
-            self.skeleton.do_verlet_timestep();
+            self.update_ragdoll(map);
             self.particle.pos = self.skeleton.pos(12);
-            //CheckSkeletonOutOfBounds;
+
+            self.respawn_counter -= 1;
+
+            if self.respawn_counter <= 0 {
+                self.respawn(map.team_spawnpoint(self.team), state.realistic_mode);
+            }
+        } else if self.health <= 0.0 || map.out_of_bounds(self.particle.pos) {
+            self.die(emitter, state.realistic_mode);
         }
 
         if self.particle.velocity.x > MAX_VELOCITY {
@@ -451,7 +824,29 @@ impl Soldier {
         }
     }
 
-    pub fn check_map_collision(&mut self, map: &MapFile, x: f32, y: f32, area: i32) -> bool {
+    // Steps the dead body on its own, so it only needs the map. The body is removed
+    // (made inactive) once any part of it leaves the map bounds.
+    pub fn update_ragdoll(&mut self, map: &MapFile) {
+        if !self.active {
+            return;
+        }
+
+        self.skeleton.do_verlet_timestep();
+
+        for i in 1..=self.skeleton.particles().len() {
+            if self.skeleton.active(i) {
+                let (x, y) = self.skeleton.pos(i).into();
+                self.check_skeleton_map_collision(map, i, x, y);
+
+                if map.out_of_bounds(self.skeleton.pos(i)) {
+                    self.active = false;
+                }
+            }
+        }
+    }
+
+    pub fn check_map_collision(&mut self, state: &MainState, x: f32, y: f32, area: i32) -> bool {
+        let map = &state.map;
         let pos = vec2(x, y) + self.particle.velocity;
         let rx = ((pos.x / map.sectors_division as f32).round()) as i32 + 25;
         let ry = ((pos.y / map.sectors_division as f32).round()) as i32 + 25;
@@ -461,10 +856,10 @@ impl Soldier {
                 let poly = map.sectors_poly[rx as usize][ry as usize].polys[j] as usize - 1;
                 let polytype = map.polygons[poly].polytype;
 
-                if polytype != PolyType::NoCollide && polytype != PolyType::OnlyBulletsCollide {
+                if map.polygons[poly].player_collides(self.team, self.has_flag) {
                     let mut polygons = map.polygons[poly];
                     if map.point_in_poly(pos, &mut polygons) {
-                        self.handle_special_polytypes(map, polytype, pos);
+                        self.handle_special_polytypes(state, polytype, pos);
 
                         let mut dist = 0.0;
                         let mut k = 0;
@@ -571,12 +966,13 @@ impl Soldier {
 
     pub fn check_map_vertices_collision(
         &mut self,
-        map: &MapFile,
+        state: &MainState,
         x: f32,
         y: f32,
         r: f32,
         has_collided: bool,
     ) -> bool {
+        let map = &state.map;
         let pos = vec2(x, y) + self.particle.velocity;
         let rx = ((pos.x / map.sectors_division as f32).round()) as i32 + 25;
         let ry = ((pos.y / map.sectors_division as f32).round()) as i32 + 25;
@@ -586,7 +982,7 @@ impl Soldier {
                 let poly = map.sectors_poly[rx as usize][ry as usize].polys[j] as usize - 1;
                 let polytype = map.polygons[poly].polytype;
 
-                if polytype != PolyType::NoCollide && polytype != PolyType::OnlyBulletsCollide {
+                if map.polygons[poly].player_collides(self.team, self.has_flag) {
                     for i in 0..3 {
                         let vert = vec2(
                             map.polygons[poly].vertices[i].x,
@@ -596,7 +992,7 @@ impl Soldier {
                         let dist = distance(vert, pos);
                         if dist < r {
                             if !has_collided {
-                                self.handle_special_polytypes(map, polytype, pos);
+                                self.handle_special_polytypes(state, polytype, pos);
                             }
                             let mut dir = pos - vert;
                             dir = vec2normalize(dir);
@@ -612,11 +1008,12 @@ impl Soldier {
 
     pub fn check_radius_map_collision(
         &mut self,
-        map: &MapFile,
+        state: &MainState,
         x: f32,
         y: f32,
         has_collided: bool,
     ) -> bool {
+        let map = &state.map;
         let mut s_pos = vec2(x, y - 3.0);
 
         let mut det_acc = vec2length(self.particle.velocity).trunc() as i32;
@@ -637,7 +1034,7 @@ impl Soldier {
                     let poly = map.sectors_poly[rx as usize][ry as usize].polys[j] as usize - 1;
                     let polytype = map.polygons[poly].polytype;
 
-                    if polytype != PolyType::NoCollide && polytype != PolyType::OnlyBulletsCollide {
+                    if map.polygons[poly].player_collides(self.team, self.has_flag) {
                         for k in 0..2 {
                             let mut norm = map.perps[poly][k];
                             norm *= -SOLDIER_COL_RADIUS;
@@ -646,7 +1043,7 @@ impl Soldier {
 
                             if map.point_in_poly_edges(pos.x, pos.y, poly as i32) {
                                 if !has_collided {
-                                    self.handle_special_polytypes(map, polytype, pos);
+                                    self.handle_special_polytypes(state, polytype, pos);
                                 }
                                 let mut d = 0.0;
                                 let mut b = 0;
@@ -723,7 +1120,9 @@ impl Soldier {
             for j in 0..map.sectors_poly[rx as usize][ry as usize].polys.len() {
                 let poly = map.sectors_poly[rx as usize][ry as usize].polys[j] - 1;
 
-                if map.point_in_poly_edges(pos.x, pos.y, i32::from(poly)) {
+                if map.polygons[poly as usize].player_collides(self.team, self.has_flag)
+                    && map.point_in_poly_edges(pos.x, pos.y, i32::from(poly))
+                {
                     let mut dist = 0.0;
                     let mut b = 0;
                     let mut perp =
@@ -745,8 +1144,9 @@ impl Soldier {
             if (rx > 0) && (rx < map.sectors_num + 25) && (ry > 0) && (ry < map.sectors_num + 25) {
                 for j in 0..map.sectors_poly[rx as usize][ry as usize].polys.len() {
                     let poly = map.sectors_poly[rx as usize][ry as usize].polys[j] - 1;
-                    //if (Map.PolyType[poly] <> POLY_TYPE_DOESNT) and (Map.PolyType[poly] <> POLY_TYPE_ONLY_BULLETS) then
-                    if map.point_in_poly_edges(pos.x, pos.y, i32::from(poly)) {
+                    if map.polygons[poly as usize].player_collides(self.team, self.has_flag)
+                        && map.point_in_poly_edges(pos.x, pos.y, i32::from(poly))
+                    {
                         let mut dist = 0.0;
                         let mut b = 0;
                         let mut perp =
@@ -764,10 +1164,10 @@ impl Soldier {
         result
     }
 
-    pub fn fire(&self, emitter: &mut Vec<EmitterItem>) {
+    pub fn fire(&self, state: &MainState, emitter: &mut Vec<EmitterItem>) {
         let weapon = self.primary_weapon();
 
-        let dir = {
+        let mut dir = {
             if weapon.bullet_style == BulletStyle::Blade || self.body_animation.id == Anim::Mercy
                 || self.body_animation.id == Anim::Mercy2
             {
@@ -779,6 +1179,16 @@ impl Soldier {
             }
         };
 
+        // the Barrett is only accurate when fired from a crouch or prone
+        if weapon.kind == WeaponKind::Barrett
+            && state.config.barrett().stance_penalty
+            && !self.crouched_or_prone()
+        {
+            let angle = (rand::random::<f32>() * 2.0 - 1.0) * BARRETT_STAND_SPREAD;
+            let (sin, cos) = angle.sin_cos();
+            dir = vec2(dir.x * cos - dir.y * sin, dir.x * sin + dir.y * cos);
+        }
+
         let pos = self.skeleton.pos(15) + dir * 4.0 - vec2(0.0, 2.0);
         let bullet_velocity = dir * weapon.speed;
         let inherited_velocity = self.particle.velocity * weapon.inherited_velocity;
@@ -790,10 +1200,15 @@ impl Soldier {
             velocity: bullet_velocity + inherited_velocity,
             timeout: weapon.timeout as i16,
             hit_multiply: weapon.hit_multiply,
-            team: Team::None,
+            team: self.team,
+            owner: self.num,
             sprite: weapon.bullet_sprite,
         };
 
+        if self.bonus == Some(Bonus::Berserker) {
+            params.hit_multiply *= BERSERKER_DAMAGE;
+        }
+
         match weapon.kind {
             WeaponKind::DesertEagles => {
                 emitter.push(EmitterItem::Bullet(params));
@@ -805,7 +1220,6 @@ impl Soldier {
                 emitter.push(EmitterItem::Bullet(params));
             }
             WeaponKind::Spas12 => {}
-            WeaponKind::Flamer => {}
             WeaponKind::NoWeapon => {}
             WeaponKind::Knife => {}
             WeaponKind::Chainsaw => {}
@@ -814,3 +1228,65 @@ impl Soldier {
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::*;
+
+    fn dead_soldier(state: &MainState) -> Soldier {
+        let mut soldiers = Vec::new();
+        let mut emitter = Vec::new();
+        add_soldier(state, &mut soldiers, Team::Alpha);
+
+        let mut soldier = soldiers.remove(0);
+
+        // let it land first so it dies standing on the ground
+        for _ in 0..120 {
+            soldier.update(state, &mut emitter);
+        }
+
+        soldier.die(&mut emitter, state.realistic_mode);
+        soldier
+    }
+
+    #[test]
+    fn ragdoll_settles_on_the_ground() {
+        let state = test_state(GameMode::CaptureTheFlag);
+        let mut soldier = dead_soldier(&state);
+        let start = soldier.skeleton.pos(12);
+
+        for _ in 0..600 {
+            soldier.update_ragdoll(&state.map);
+        }
+
+        let sk = &soldier.skeleton;
+        let motion = (1..=sk.particles().len())
+            .filter(|i| sk.active(*i))
+            .map(|i| distance(sk.pos(i), sk.old_pos(i)))
+            .fold(0.0f32, f32::max);
+
+        assert!(soldier.active);
+        assert!(motion < 0.5, "body still moving by {}", motion);
+        assert!(
+            distance(sk.pos(12), start) < 60.0,
+            "body fell through the map"
+        );
+    }
+
+    #[test]
+    fn ragdoll_leaving_the_map_is_removed() {
+        let state = test_state(GameMode::CaptureTheFlag);
+        let mut soldier = dead_soldier(&state);
+        let edge = (state.map.sectors_num * state.map.sectors_division) as f32;
+
+        // thrown past the bottom edge of the map
+        for i in 1..=soldier.skeleton.particles().len() {
+            *soldier.skeleton.pos_mut(i) += vec2(0.0, 2.0 * edge);
+            *soldier.skeleton.old_pos_mut(i) += vec2(0.0, 2.0 * edge);
+        }
+
+        soldier.update_ragdoll(&state.map);
+        assert!(!soldier.active);
+    }
+}
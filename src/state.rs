@@ -12,10 +12,14 @@ pub enum Team {
 #[derive(Debug, Copy, Clone)]
 pub enum EmitterItem {
     Bullet(BulletParams),
+    Thing(ThingParams),
+    Explosion(ExplosionParams),
+    Kill(KillParams),
 }
 
 pub struct MainState {
     pub map: MapFile,
+    pub config: ServerConfig,
     pub camera: Vec2,
     pub camera_prev: Vec2,
     pub mouse: Vec2,
@@ -24,7 +28,19 @@ pub struct MainState {
     pub game_height: f32,
     pub gravity: f32,
     pub zoom: f32,
+    pub timescale: f32,
+    pub realistic_mode: bool,
     pub bullets: Vec<Bullet>,
+    pub things: Vec<Thing>,
+    pub stationary_guns: Vec<StationaryGun>,
+    pub kits: KitSpawner,
+    pub round: GameRound,
+    pub flags: Vec<Flag>,
+    pub paths: PathFinder,
+    pub debug: DebugOptions,
+    pub bullet_traces: BulletTraces,
+    pub kill_feed: KillFeed,
+    pub chat: Chat,
 }
 
 impl Default for Team {
@@ -32,3 +48,219 @@ impl Default for Team {
         Team::None
     }
 }
+
+impl Team {
+    pub fn values() -> &'static [Team] {
+        static VALUES: &[Team] = &[Team::Alpha, Team::Bravo, Team::Charlie, Team::Delta];
+        VALUES
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn color(&self) -> Option<gfx2d::Color> {
+        match *self {
+            Team::None => None,
+            Team::Alpha => Some(gfx2d::rgb(200, 0, 0)),
+            Team::Bravo => Some(gfx2d::rgb(0, 0, 200)),
+            Team::Charlie => Some(gfx2d::rgb(200, 200, 0)),
+            Team::Delta => Some(gfx2d::rgb(0, 200, 0)),
+        }
+    }
+
+    // Players joining a team game go to the team with the fewest players, ties go to
+    // the first team.
+    pub fn balanced(soldiers: &[Soldier], teams: usize) -> Team {
+        if teams == 0 {
+            return Team::None;
+        }
+
+        *Team::values()[..teams]
+            .iter()
+            .min_by_key(|team| soldiers.iter().filter(|s| s.team == **team).count())
+            .unwrap()
+    }
+}
+
+impl MainState {
+    // A round about to start on the map, with a 640x480 view.
+    pub fn new(map: MapFile, config: ServerConfig) -> MainState {
+        let round = GameRound::new(&map, &config);
+        let paths = PathFinder::new(&map);
+
+        let mut state = MainState {
+            map,
+            config,
+            game_width: 640.0,
+            game_height: 480.0,
+            camera: Vec2::zero(),
+            camera_prev: Vec2::zero(),
+            mouse: Vec2::zero(),
+            mouse_prev: Vec2::zero(),
+            gravity: GRAV,
+            zoom: 0.0,
+            timescale: 1.0,
+            realistic_mode: false,
+            bullets: vec![],
+            things: vec![],
+            stationary_guns: vec![],
+            kits: KitSpawner::new(),
+            round,
+            flags: vec![],
+            paths,
+            debug: DebugOptions::default(),
+            bullet_traces: BulletTraces::default(),
+            kill_feed: KillFeed::default(),
+            chat: Chat::new(),
+        };
+
+        state.start_round();
+        state
+    }
+
+    // Game rules that can't change mid-round are picked up from the config here.
+    pub fn start_round(&mut self) {
+        self.realistic_mode = self.config.realistic_mode;
+        self.bullets.clear();
+        self.things.clear();
+        self.stationary_guns = StationaryGun::from_map(&self.map, self.realistic_mode);
+        self.kits = KitSpawner::new();
+        self.round = GameRound::new(&self.map, &self.config);
+        self.flags = Flag::from_map(&self.map, self.config.game_mode);
+    }
+
+    // One tick of the game, bots think before the soldiers they control move. Finished
+    // rounds are left for the caller to restart.
+    pub fn update(
+        &mut self,
+        soldiers: &mut [Soldier],
+        bots: &mut [Bot],
+        emitter: &mut Vec<EmitterItem>,
+    ) {
+        // remove inactive bullets
+
+        let mut i = 0;
+        while i < self.bullets.len() {
+            if !self.bullets[i].active {
+                self.bullets.swap_remove(i);
+            } else {
+                i += 1;
+            }
+        }
+
+        // remove inactive things
+
+        let mut i = 0;
+        while i < self.things.len() {
+            if !self.things[i].active {
+                self.things.swap_remove(i);
+            } else {
+                i += 1;
+            }
+        }
+
+        // update soldiers
+
+        for bot in bots.iter_mut() {
+            if let Some(i) = soldiers.iter().position(|s| s.num == bot.num) {
+                let mut control = std::mem::take(&mut soldiers[i].control);
+                bot.update(&mut control, &soldiers[i], soldiers, self);
+                soldiers[i].control = control;
+            }
+        }
+
+        for soldier in soldiers.iter_mut() {
+            soldier.has_flag = self.flags.iter().any(|f| f.holder == Some(soldier.num));
+            soldier.update(self, emitter);
+        }
+
+        // update bullets
+
+        for bullet in self.bullets.iter_mut() {
+            let from = bullet.particle.pos;
+            let impact = bullet.update(&self.map, self.realistic_mode, emitter);
+
+            if self.debug.bullets {
+                self.bullet_traces.add_segment(from, bullet.particle.pos);
+
+                if let Some(pos) = impact {
+                    self.bullet_traces.add_impact(pos);
+                }
+            }
+
+            for soldier in soldiers.iter_mut() {
+                let hit =
+                    bullet.check_soldier_collision(soldier, self.config.friendly_fire, emitter);
+
+                if hit && self.debug.bullets {
+                    self.bullet_traces.add_impact(bullet.particle.pos);
+                }
+            }
+        }
+
+        self.bullet_traces.update();
+        self.kill_feed.update();
+        self.chat.update();
+
+        // update stationary guns
+
+        for (i, gun) in self.stationary_guns.iter_mut().enumerate() {
+            gun.update();
+
+            for soldier in soldiers.iter_mut() {
+                gun.update_user(i, soldier, emitter);
+            }
+        }
+
+        // update things
+
+        self.kits
+            .update(&self.map, &self.things, &self.config, emitter);
+
+        for thing in self.things.iter_mut() {
+            thing.update(&self.map, self.gravity);
+
+            for soldier in soldiers.iter_mut() {
+                thing.check_soldier_collision(soldier, self.realistic_mode);
+            }
+        }
+
+        // update flags and game rules
+
+        for flag in self.flags.iter_mut() {
+            flag.update(&self.map, self.gravity, soldiers);
+
+            for soldier in soldiers.iter() {
+                flag.check_soldier_collision(soldier);
+            }
+        }
+
+        self.round.update(
+            &self.map,
+            &mut self.flags,
+            &self.things,
+            soldiers,
+            self.realistic_mode,
+            emitter,
+        );
+
+        // create emitted objects
+
+        for item in emitter.drain(..) {
+            match item {
+                EmitterItem::Bullet(params) => self.bullets.push(Bullet::new(&params)),
+                EmitterItem::Thing(params) => self.things.push(Thing::new(&params)),
+                EmitterItem::Explosion(params) => {
+                    for soldier in soldiers.iter_mut() {
+                        soldier.explosion_hit(&params, self.config.friendly_fire);
+                    }
+                }
+                EmitterItem::Kill(params) => {
+                    self.kill_feed.add(&params);
+                    self.round.on_kill(&params, soldiers, &self.flags);
+                }
+            };
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use super::*;
+
+const USE_RADIUS: f32 = 20.0;
+const LEAVE_RADIUS: f32 = 30.0;
+const MAX_ARC: f32 = PI / 3.0;
+const MUZZLE_OFFSET: f32 = 22.0;
+const HEAT_PER_SHOT: f32 = 0.06;
+const COOLING: f32 = 0.004;
+const COOLED_DOWN: f32 = 0.3;
+
+#[derive(Debug, Copy, Clone)]
+pub struct StationaryGun {
+    pub pos: Vec2,
+    pub direction: i8,
+    pub angle: f32,
+    pub angle_prev: f32,
+    pub user: Option<usize>,
+    pub weapon: Weapon,
+    pub heat: f32,
+    pub overheated: bool,
+}
+
+impl StationaryGun {
+    pub fn new(pos: Vec2, realistic: bool) -> StationaryGun {
+        let mut weapon = Weapon::new(WeaponKind::M2, realistic);
+        weapon.ammo_count = weapon.ammo;
+
+        StationaryGun {
+            pos,
+            direction: 1,
+            angle: 0.0,
+            angle_prev: 0.0,
+            user: None,
+            weapon,
+            heat: 0.0,
+            overheated: false,
+        }
+    }
+
+    pub fn from_map(map: &MapFile, realistic: bool) -> Vec<StationaryGun> {
+        map.spawnpoints
+            .iter()
+            .filter(|spawn| spawn.active && spawn.kind() == SpawnpointKind::StationaryGun)
+            .map(|spawn| StationaryGun::new(vec2(spawn.x as f32, spawn.y as f32), realistic))
+            .collect()
+    }
+
+    pub fn aim_dir(&self) -> Vec2 {
+        vec2(
+            f32::from(self.direction) * self.angle.cos(),
+            self.angle.sin(),
+        )
+    }
+
+    pub fn update(&mut self) {
+        self.angle_prev = self.angle;
+        self.heat = f32::max(0.0, self.heat - COOLING);
+
+        if self.overheated && self.heat < COOLED_DOWN {
+            self.overheated = false;
+        }
+
+        if self.weapon.fire_interval_count > 0 {
+            self.weapon.fire_interval_count -= 1;
+        }
+
+        if self.weapon.ammo_count == 0 {
+            if self.weapon.reload_time_count > 0 {
+                self.weapon.reload_time_count -= 1;
+            } else {
+                self.weapon.ammo_count = self.weapon.ammo;
+            }
+        }
+    }
+
+    // Handles mounting, leaving and firing for one soldier. The gun is released when its
+    // user dies, walks away, jumps, jets or presses the use key again.
+    pub fn update_user(
+        &mut self,
+        index: usize,
+        soldier: &mut Soldier,
+        emitter: &mut Vec<EmitterItem>,
+    ) {
+        let in_range = distance(soldier.particle.pos, self.pos) < USE_RADIUS;
+
+        if self.user.is_none() {
+            if soldier.control.use_item
+                && in_range
+                && !soldier.dead_meat
+                && soldier.stat_gun.is_none()
+            {
+                soldier.control.use_item = false;
+                soldier.stat_gun = Some(index);
+                self.user = Some(soldier.num);
+            }
+
+            return;
+        }
+
+        if self.user != Some(soldier.num) {
+            return;
+        }
+
+        let c = &soldier.control;
+        let moving = c.left || c.right || c.up || c.jets;
+
+        if soldier.dead_meat
+            || moving
+            || c.use_item
+            || distance(soldier.particle.pos, self.pos) > LEAVE_RADIUS
+        {
+            soldier.control.use_item = false;
+            soldier.stat_gun = None;
+            self.user = None;
+            return;
+        }
+
+        let aim = vec2(c.mouse_aim_x as f32, c.mouse_aim_y as f32) - self.pos;
+
+        if aim.x != 0.0 {
+            self.direction = iif!(aim.x > 0.0, 1, -1);
+        }
+
+        self.angle = f32::atan2(aim.y, aim.x.abs()).clamp(-MAX_ARC, MAX_ARC);
+
+        if soldier.control.fire
+            && !self.overheated
+            && self.weapon.ammo_count > 0
+            && self.weapon.fire_interval_count == 0
+        {
+            self.fire(soldier.num, soldier.team, emitter);
+        }
+    }
+
+    fn fire(&mut self, owner: usize, team: Team, emitter: &mut Vec<EmitterItem>) {
+        let dir = self.aim_dir();
+        let weapon = &mut self.weapon;
+
+        emitter.push(EmitterItem::Bullet(BulletParams {
+            style: weapon.bullet_style,
+            weapon: weapon.kind,
+            position: self.pos + dir * MUZZLE_OFFSET,
+            velocity: dir * weapon.speed,
+            timeout: weapon.timeout as i16,
+            hit_multiply: weapon.hit_multiply,
+            team,
+            owner,
+            sprite: weapon.bullet_sprite,
+        }));
+
+        weapon.ammo_count -= 1;
+        weapon.fire_interval_count = weapon.fire_interval;
+
+        if weapon.ammo_count == 0 {
+            weapon.reload_time_count = weapon.reload_time;
+        }
+
+        self.heat += HEAT_PER_SHOT;
+
+        if self.heat >= 1.0 {
+            self.overheated = true;
+        }
+    }
+}
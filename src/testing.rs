@@ -0,0 +1,288 @@
+use super::*;
+use byteorder::{LittleEndian, WriteBytesExt};
+use std::path::Path;
+
+// A flat capture the flag map with waypoints and both flag bases, see test_map.
+pub const TEST_MAP: &str = "test.pms";
+
+const SECTOR_DIVISION: i32 = 40;
+const SECTORS_NUM: i32 = 25;
+
+// The skeleton of a standing soldier facing right, relative to its feet. The pairs
+// kept at 28 and 29 are the ones Soldier::update steps on their own.
+const GOSTEK: [(f32, f32); 24] = [
+    (-3.0, 0.0),
+    (3.0, 0.0),
+    (-2.0, -6.0),
+    (3.0, -6.0),
+    (-1.0, -12.0),
+    (1.0, -12.0),
+    (0.0, -14.0),
+    (0.0, -20.0),
+    (0.0, -26.0),
+    (-2.0, -24.0),
+    (2.0, -24.0),
+    (0.0, -30.0),
+    (-2.0, -18.0),
+    (5.0, -20.0),
+    (12.0, -21.0),
+    (8.0, -21.0),
+    (-1.0, 0.0),
+    (5.0, 0.0),
+    (6.0, -19.0),
+    (0.0, -17.0),
+    (0.0, -26.0),
+    (-3.0, -28.0),
+    (0.0, -30.0),
+    (3.0, -32.0),
+];
+
+const GOSTEK_CONSTRAINTS: [(usize, usize); 31] = [
+    (1, 3),
+    (2, 4),
+    (3, 5),
+    (4, 6),
+    (5, 6),
+    (1, 17),
+    (2, 18),
+    (5, 7),
+    (6, 7),
+    (7, 20),
+    (20, 8),
+    (8, 9),
+    (9, 12),
+    (9, 10),
+    (9, 11),
+    (10, 13),
+    (13, 19),
+    (11, 14),
+    (14, 16),
+    (16, 15),
+    (10, 11),
+    (8, 10),
+    (8, 11),
+    (5, 8),
+    (6, 8),
+    (7, 9),
+    (12, 10),
+    (21, 22),
+    (23, 24),
+    (9, 21),
+    (12, 23),
+];
+
+// Pole from the base up, then the two free corners of the cloth.
+const FLAG: [(f32, f32); 4] = [(0.0, 0.0), (0.0, -24.0), (12.0, -22.0), (12.0, -14.0)];
+const FLAG_CONSTRAINTS: [(usize, usize); 6] = [(1, 2), (2, 3), (3, 4), (4, 1), (2, 4), (1, 3)];
+
+// Stand-ins for the Soldat data that simulating a round loads, served by read_asset.
+pub fn asset(path: &Path) -> Option<Vec<u8>> {
+    let name = path.file_name()?.to_str()?;
+
+    match name {
+        "gostek.po" => Some(object(&GOSTEK, &GOSTEK_CONSTRAINTS, 4.5)),
+        "flag.po" => Some(object(&FLAG, &FLAG_CONSTRAINTS, 4.0)),
+        TEST_MAP => Some(test_map()),
+        _ if name.ends_with(".poa") => Some(standing_animation()),
+        _ => None,
+    }
+}
+
+// Written the way ParticleSystem::load_from_file reads it back, scale included.
+fn object(particles: &[(f32, f32)], constraints: &[(usize, usize)], scale: f32) -> Vec<u8> {
+    let mut text = String::new();
+
+    for (i, &(x, y)) in particles.iter().enumerate() {
+        text += &format!("P{}\n{}\n0\n{}\n", i + 1, -x * 1.2 / scale, -y / scale);
+    }
+
+    text += "CONSTRAINTS\n";
+
+    for &(a, b) in constraints {
+        text += &format!("P{}\nP{}\n", a, b);
+    }
+
+    text += "ENDFILE\n";
+    text.into_bytes()
+}
+
+// Every animation holds the skeleton's pose for more frames than the code ever jumps
+// to. Body points are placed relative to the hips, which sit 4 units higher than the
+// pelvis offset standing soldiers use.
+fn standing_animation() -> Vec<u8> {
+    let mut frame = String::new();
+
+    for (i, &(x, y)) in GOSTEK[..20].iter().enumerate() {
+        let leg = i < 6 || i == 16 || i == 17;
+        let y = iif!(leg, y, y + 4.0);
+        frame += &format!("{}\n{}\n0\n{}\n", i + 1, -1.1 * x / 3.0, -y / 3.0);
+    }
+
+    let frames = vec![frame; 40];
+    (frames.join("NEXTFRAME\n") + "ENDFILE\n").into_bytes()
+}
+
+// A floor from wall to wall with its top at y = 0, the walls at x = -600 and 600.
+// Each is a single big triangle, so the surface a soldier touches is always the
+// closest edge. Flag bases are at the ends, next to the team spawns, and waypoints
+// run along the floor.
+fn test_map() -> Vec<u8> {
+    let polygons = [
+        [(-2000.0, 0.0), (2000.0, 0.0), (0.0, 2000.0)],
+        [(-600.0, -2000.0), (-600.0, 2000.0), (-2600.0, 0.0)],
+        [(600.0, 2000.0), (600.0, -2000.0), (2600.0, 0.0)],
+    ];
+
+    let spawnpoints = [
+        (-450, -20, 1),
+        (450, -20, 2),
+        (0, -20, 0),
+        (-500, -2, 5),
+        (500, -2, 6),
+        (0, -2, 14),
+        (0, -20, 15),
+    ];
+
+    let waypoints: Vec<i32> = (0..30).map(|i| -580 + 40 * i).collect();
+
+    let mut data = Vec::new();
+    data.write_i32::<LittleEndian>(11).unwrap();
+    write_string(&mut data, "Test", 38);
+    write_string(&mut data, "banana.bmp", 24);
+    data.extend_from_slice(&[0; 8]);
+    data.write_i32::<LittleEndian>(200).unwrap();
+    data.extend_from_slice(&[0; 4]);
+    data.write_i32::<LittleEndian>(0).unwrap();
+
+    data.write_i32::<LittleEndian>(polygons.len() as i32)
+        .unwrap();
+
+    for poly in &polygons {
+        for &(x, y) in poly {
+            for value in &[x, y, 1.0, 1.0] {
+                data.write_f32::<LittleEndian>(*value).unwrap();
+            }
+
+            data.extend_from_slice(&[255; 4]);
+            data.extend_from_slice(&[0; 8]);
+        }
+
+        // edge normals pointing into the triangle
+        for i in 0..3 {
+            let a = vec2(poly[i].0, poly[i].1);
+            let b = vec2(poly[(i + 1) % 3].0, poly[(i + 1) % 3].1);
+            let c = vec2(poly[(i + 2) % 3].0, poly[(i + 2) % 3].1);
+            let mut normal = vec2normalize(vec2(a.y - b.y, b.x - a.x));
+
+            if normal.dot(c - a) < 0.0 {
+                normal = -normal;
+            }
+
+            for value in &[normal.x, normal.y, 0.0] {
+                data.write_f32::<LittleEndian>(*value).unwrap();
+            }
+        }
+
+        data.write_u8(0).unwrap();
+    }
+
+    data.write_i32::<LittleEndian>(SECTOR_DIVISION).unwrap();
+    data.write_i32::<LittleEndian>(SECTORS_NUM).unwrap();
+
+    for kx in -SECTORS_NUM..=SECTORS_NUM {
+        for ky in -SECTORS_NUM..=SECTORS_NUM {
+            let half = SECTOR_DIVISION as f32 / 2.0;
+            let (x, y) = ((kx * SECTOR_DIVISION) as f32, (ky * SECTOR_DIVISION) as f32);
+
+            let polys: Vec<u16> = (0..polygons.len())
+                .filter(|&i| {
+                    let xs = polygons[i].iter().map(|v| v.0);
+                    let ys = polygons[i].iter().map(|v| v.1);
+                    xs.clone().fold(f32::MAX, f32::min) <= x + half
+                        && xs.fold(f32::MIN, f32::max) >= x - half
+                        && ys.clone().fold(f32::MAX, f32::min) <= y + half
+                        && ys.fold(f32::MIN, f32::max) >= y - half
+                })
+                .map(|i| i as u16 + 1)
+                .collect();
+
+            data.write_u16::<LittleEndian>(polys.len() as u16).unwrap();
+
+            for poly in polys {
+                data.write_u16::<LittleEndian>(poly).unwrap();
+            }
+        }
+    }
+
+    // no props, scenery or colliders
+    data.extend_from_slice(&[0; 12]);
+
+    data.write_i32::<LittleEndian>(spawnpoints.len() as i32)
+        .unwrap();
+
+    for &(x, y, team) in &spawnpoints {
+        for value in &[1, x, y, team] {
+            data.write_i32::<LittleEndian>(*value).unwrap();
+        }
+    }
+
+    data.write_i32::<LittleEndian>(waypoints.len() as i32)
+        .unwrap();
+
+    for (i, &x) in waypoints.iter().enumerate() {
+        let id = i as i32 + 1;
+        let mut connections = Vec::new();
+
+        if i > 0 {
+            connections.push(id - 1);
+        }
+
+        if i + 1 < waypoints.len() {
+            connections.push(id + 1);
+        }
+
+        data.write_u8(1).unwrap();
+        data.extend_from_slice(&[0; 3]);
+
+        for value in &[id, x, -12] {
+            data.write_i32::<LittleEndian>(*value).unwrap();
+        }
+
+        data.extend_from_slice(&[0, 0, 0, 0, 0, 1, 0]);
+        data.extend_from_slice(&[0; 5]);
+        data.write_i32::<LittleEndian>(connections.len() as i32)
+            .unwrap();
+
+        for k in 0..MAX_CONNECTIONS {
+            let id = connections.get(k).cloned().unwrap_or(0);
+            data.write_i32::<LittleEndian>(id).unwrap();
+        }
+    }
+
+    data
+}
+
+fn write_string(data: &mut Vec<u8>, text: &str, length: usize) {
+    data.push(text.len() as u8);
+    data.extend_from_slice(text.as_bytes());
+    data.resize(data.len() + length - text.len(), 0);
+}
+
+pub fn test_state(mode: GameMode) -> MainState {
+    let mut config = ServerConfig::default();
+    config.game_mode = mode;
+    config.time_limit = None;
+
+    MainState::new(MapFile::load_map_file(TEST_MAP), config)
+}
+
+// Adds a soldier at one of the team's spawnpoints and returns its number.
+pub fn add_soldier(state: &MainState, soldiers: &mut Vec<Soldier>, team: Team) -> usize {
+    let num = soldiers.iter().map(|s| s.num + 1).max().unwrap_or(0);
+    let mut soldier = Soldier::new(state.map.team_spawnpoint(team), state.realistic_mode);
+    soldier.num = num;
+    soldier.team = team;
+    soldier.player.name = format!("Soldier {}", num);
+    soldiers.push(soldier);
+    num
+}
@@ -0,0 +1,173 @@
+use super::*;
+
+const SECOND: i32 = 60;
+const WEAPON_TIMEOUT: i32 = SECOND * 30;
+const KNIFE_TIMEOUT: i32 = SECOND * 60;
+const PICKUP_DELAY: i32 = SECOND;
+const PICKUP_RADIUS: f32 = 16.0;
+const BOUNCINESS: f32 = 0.3;
+const GROUND_FRICTION: f32 = 0.9;
+
+#[derive(Debug, Copy, Clone)]
+pub enum ThingKind {
+    Weapon(Weapon),
+    Kit(KitKind),
+    HeadCap(HeadCap, gfx2d::Color),
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ThingParams {
+    pub kind: ThingKind,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub rotation: f32,
+    pub owner: usize,
+    pub stuck: bool,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Thing {
+    pub active: bool,
+    pub kind: ThingKind,
+    pub particle: Particle,
+    pub rotation: f32,
+    pub rotation_prev: f32,
+    pub on_ground: bool,
+    pub stuck: bool,
+    pub owner: usize,
+    pub pickup_delay: i32,
+    pub timeout: i32,
+}
+
+impl Thing {
+    pub fn new(params: &ThingParams) -> Thing {
+        let particle = Particle {
+            active: true,
+            pos: params.position,
+            old_pos: params.position,
+            velocity: params.velocity,
+            one_over_mass: 1.0,
+            timestep: 1.0,
+            gravity: GRAV,
+            e_damping: 0.99,
+            ..Default::default()
+        };
+
+        let timeout = match params.kind {
+            ThingKind::Weapon(weapon) if weapon.kind == WeaponKind::Knife => KNIFE_TIMEOUT,
+            // the rambo bow stays in play until someone picks it up
+            ThingKind::Weapon(weapon)
+                if weapon.is_any(&[WeaponKind::Bow, WeaponKind::FlameBow]) =>
+            {
+                i32::MAX
+            }
+            ThingKind::Weapon(_) | ThingKind::HeadCap(..) => WEAPON_TIMEOUT,
+            ThingKind::Kit(kit) => kit.timeout(),
+        };
+
+        Thing {
+            active: true,
+            kind: params.kind,
+            particle,
+            rotation: params.rotation,
+            rotation_prev: params.rotation,
+            on_ground: false,
+            stuck: params.stuck,
+            owner: params.owner,
+            pickup_delay: PICKUP_DELAY,
+            timeout,
+        }
+    }
+
+    // Gravity follows sv_gravity like it does for soldiers and flags.
+    pub fn update(&mut self, map: &MapFile, gravity: f32) {
+        self.rotation_prev = self.rotation;
+        self.particle.gravity = gravity;
+
+        if self.stuck {
+            self.particle.old_pos = self.particle.pos;
+        } else {
+            self.particle.euler();
+            self.on_ground = self.check_map_collision(map);
+
+            if self.on_ground {
+                // settle flat on whichever side the thing landed
+                let target = (self.rotation / PI).round() * PI;
+                self.rotation = lerp(self.rotation, target, 0.3);
+            } else {
+                self.rotation += self.particle.velocity.x / 8.0;
+            }
+        }
+
+        if self.pickup_delay > 0 {
+            self.pickup_delay -= 1;
+        }
+
+        self.timeout -= 1;
+
+        if self.timeout <= 0 {
+            self.active = false;
+        }
+
+        if map.out_of_bounds(self.particle.pos) {
+            self.active = false;
+        }
+    }
+
+    pub fn check_map_collision(&mut self, map: &MapFile) -> bool {
+        let pos = self.particle.pos;
+        let mut result = false;
+
+        for p in map.sector_polys(pos) {
+            let p = (*p - 1) as usize;
+
+            if map.polygons[p].thing_collides() && map.point_in_poly_edges(pos.x, pos.y, p as i32) {
+                let mut dist = 0.0;
+                let mut k = 0;
+                let perp =
+                    vec2normalize(map.closest_perpendicular(p as i32, pos, &mut dist, &mut k));
+                let into_poly = self.particle.velocity.dot(perp);
+
+                self.particle.pos -= perp * dist;
+
+                if into_poly > 0.0 {
+                    self.particle.velocity -= perp * into_poly * (1.0 + BOUNCINESS);
+                }
+
+                self.particle.velocity.x *= GROUND_FRICTION;
+                result = true;
+                break;
+            }
+        }
+
+        result
+    }
+
+    pub fn check_soldier_collision(&mut self, soldier: &mut Soldier, realistic: bool) -> bool {
+        if !self.active || soldier.dead_meat {
+            return false;
+        }
+
+        if self.pickup_delay > 0 && self.owner == soldier.num {
+            return false;
+        }
+
+        let center = soldier.particle.pos - vec2(0.0, 10.0);
+
+        if distance(self.particle.pos, center) > PICKUP_RADIUS {
+            return false;
+        }
+
+        let picked = match self.kind {
+            ThingKind::Weapon(weapon) => soldier.pick_up_weapon(weapon, realistic),
+            ThingKind::Kit(kit) => soldier.pick_up_kit(kit, realistic),
+            ThingKind::HeadCap(..) => false,
+        };
+
+        if picked {
+            self.active = false;
+        }
+
+        picked
+    }
+}
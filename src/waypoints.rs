@@ -0,0 +1,218 @@
+use super::*;
+
+const NODE_SPACING: f32 = 40.0;
+const NODE_HEIGHT: f32 = 12.0;
+const SOLDIER_HEIGHT: f32 = 30.0;
+const MAX_FLOOR_SLOPE: f32 = 0.7;
+const MAX_FALL: f32 = 400.0;
+const CLEARANCE_STEP: f32 = 6.0;
+
+// ticks of the jump animation that push the soldier up
+const JUMP_TICKS: f32 = 6.0;
+// the estimates below assume perfect input, bots don't have that
+const SAFETY: f32 = 0.6;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord)]
+enum Move {
+    Walk,
+    Jump,
+    Jet,
+}
+
+// How far a soldier gets on the ground, with a jump and with a full jet tank, worked
+// out from the movement constants in control.rs and the map's jet fuel.
+struct Reach {
+    run_speed: f32,
+    jump_height: f32,
+    jump_distance: f32,
+    jet_height: f32,
+    jet_distance: f32,
+}
+
+impl Reach {
+    fn new(map: &MapFile) -> Reach {
+        let run_speed = RUNSPEED / (1.0 - SURFACECOEFX);
+
+        let jump_speed = JUMP_TICKS * (JUMPSPEED - GRAV);
+        let jump_time = 2.0 * jump_speed / GRAV;
+        let jump_height = SAFETY * jump_speed * jump_speed / (2.0 * GRAV);
+
+        let jet_time = map.start_jet.max(0) as f32;
+        let jet_height = SAFETY * 0.5 * (JETSPEED - GRAV).max(0.0) * jet_time * jet_time;
+
+        Reach {
+            run_speed,
+            jump_height,
+            jump_distance: SAFETY * run_speed * jump_time,
+            jet_height: jump_height + jet_height,
+            jet_distance: SAFETY * run_speed * (jump_time + jet_time),
+        }
+    }
+
+    fn classify(&self, from: Vec2, to: Vec2) -> Option<Move> {
+        let dx = (to.x - from.x).abs();
+        let rise = from.y - to.y;
+
+        if rise <= NODE_HEIGHT {
+            // walking along a surface or dropping down from a ledge
+            let fall = (-rise).max(0.0);
+
+            if fall <= MAX_FALL && dx <= 1.5 * NODE_SPACING + fall * self.run_speed / 4.0 {
+                return Some(Move::Walk);
+            }
+        }
+
+        if rise <= self.jump_height && dx <= self.jump_distance {
+            return Some(Move::Jump);
+        }
+
+        if rise <= self.jet_height && dx <= self.jet_distance {
+            return Some(Move::Jet);
+        }
+
+        None
+    }
+}
+
+// Builds a waypoint graph from the map geometry for maps without one. Nodes go on top
+// of every floor a soldier can stand on, connections are the walks, jumps and jets
+// between them that the movement code can pull off.
+pub fn generate_waypoints(map: &MapFile) -> Vec<MapWaypoint> {
+    let reach = Reach::new(map);
+    let nodes = floor_nodes(map);
+
+    let mut waypoints: Vec<MapWaypoint> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, pos)| MapWaypoint {
+            active: true,
+            id: i as i32 + 1,
+            x: pos.x.round() as i32,
+            y: pos.y.round() as i32,
+            left: false,
+            right: false,
+            up: false,
+            down: false,
+            jetpack: false,
+            path_num: 1,
+            special_action: 0,
+            connections: Vec::new(),
+        })
+        .collect();
+
+    // the cheapest move that reaches a node decides which keys bots press to get there
+    let mut needs: Vec<Option<Move>> = vec![None; nodes.len()];
+
+    for (i, from) in nodes.iter().enumerate() {
+        let mut links: Vec<(usize, Move)> = nodes
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .filter_map(|(j, to)| reach.classify(*from, *to).map(|m| (j, m)))
+            .filter(|&(j, _)| clear_path(map, *from, nodes[j]))
+            .collect();
+
+        links.sort_by(|a, b| {
+            let da = distance(*from, nodes[a.0]);
+            let db = distance(*from, nodes[b.0]);
+            a.1.cmp(&b.1).then(da.partial_cmp(&db).unwrap())
+        });
+
+        links.truncate(MAX_CONNECTIONS);
+
+        for &(j, m) in &links {
+            needs[j] = Some(needs[j].map_or(m, |n| n.min(m)));
+        }
+
+        waypoints[i].connections = links.iter().map(|&(j, _)| j).collect();
+    }
+
+    for (waypoint, need) in waypoints.iter_mut().zip(needs) {
+        waypoint.up = need.is_some_and(|m| m != Move::Walk);
+        waypoint.jetpack = need == Some(Move::Jet);
+    }
+
+    waypoints
+}
+
+// Polygons a soldier can stand on for at least some team or flag state. Floors that
+// kill on touch get no nodes.
+fn walkable(poly: &MapPolygon) -> bool {
+    let hazard = matches!(
+        poly.polytype,
+        PolyType::Deadly | PolyType::BloodyDeadly | PolyType::Explosive | PolyType::Lava
+    );
+
+    let collides = [
+        Team::None,
+        Team::Alpha,
+        Team::Bravo,
+        Team::Charlie,
+        Team::Delta,
+    ]
+    .iter()
+    .any(|team| poly.player_collides(*team, false) || poly.player_collides(*team, true));
+
+    collides && !hazard
+}
+
+fn blocked(map: &MapFile, pos: Vec2) -> bool {
+    map.sector_polys(pos).iter().any(|poly| {
+        let poly = (*poly - 1) as usize;
+        map.polygons[poly].player_collides(Team::None, false)
+            && map.point_in_poly_edges(pos.x, pos.y, poly as i32)
+    })
+}
+
+fn clear_path(map: &MapFile, a: Vec2, b: Vec2) -> bool {
+    let steps = i32::max(1, f32::ceil(distance(a, b) / CLEARANCE_STEP) as i32);
+    (0..=steps).all(|i| !blocked(map, lerp(a, b, i as f32 / steps as f32)))
+}
+
+fn floor_nodes(map: &MapFile) -> Vec<Vec2> {
+    let mut nodes: Vec<Vec2> = Vec::new();
+
+    for poly in map.polygons.iter().filter(|poly| walkable(poly)) {
+        let v: Vec<Vec2> = poly.vertices.iter().map(|v| vec2(v.x, v.y)).collect();
+
+        for k in 0..3 {
+            let (a, b, c) = (v[k], v[(k + 1) % 3], v[(k + 2) % 3]);
+            let edge = b - a;
+            let length = distance(a, b);
+
+            if length < 1.0 {
+                continue;
+            }
+
+            // outward normal of the edge, a floor faces up (y grows downwards)
+            let mut normal = vec2(edge.y, -edge.x) / length;
+
+            if (c - a).x * normal.x + (c - a).y * normal.y > 0.0 {
+                normal = -normal;
+            }
+
+            if normal.y > -MAX_FLOOR_SLOPE {
+                continue;
+            }
+
+            let count = f32::max(1.0, (length / NODE_SPACING).floor()) as i32;
+
+            for i in 0..count {
+                let ground = lerp(a, b, (i as f32 + 0.5) / count as f32);
+                let pos = ground - vec2(0.0, NODE_HEIGHT);
+
+                if blocked(map, pos) || blocked(map, pos - vec2(0.0, SOLDIER_HEIGHT)) {
+                    continue;
+                }
+
+                if nodes.iter().any(|n| distance(*n, pos) < NODE_SPACING / 2.0) {
+                    continue;
+                }
+
+                nodes.push(pos);
+            }
+        }
+    }
+
+    nodes
+}